@@ -0,0 +1,76 @@
+use anyhow::anyhow;
+
+use crate::{error::report, shell::Shell};
+
+use super::{parse_options, quote, BuiltIn};
+
+pub struct Alias {}
+
+pub struct Unalias {}
+
+/// Whether `name` can be used for an alias, it must be a plain word.
+fn is_alias_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name
+            .chars()
+            .any(|c| c.is_whitespace() || "/$`=|&;()<>'\"\\".contains(c))
+}
+
+fn print_alias(name: &str, value: &str) {
+    println!("alias {name}={}", quote(value));
+}
+
+impl BuiltIn for Alias {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        let (options, names) = parse_options(args, "p")?;
+        if names.is_empty() || options.contains(&('p', true)) {
+            for (name, value) in shell.aliases() {
+                print_alias(name, value);
+            }
+        }
+
+        let mut code = 0;
+        for arg in names {
+            match arg.split_once('=') {
+                Some((name, value)) => {
+                    if !is_alias_name(name) {
+                        return Err(anyhow!("`{name}': invalid alias name"));
+                    }
+                    shell
+                        .aliases_mut()
+                        .insert(name.to_string(), value.to_string());
+                }
+                None => match shell.aliases().get(arg) {
+                    Some(value) => print_alias(arg, value),
+                    None => {
+                        report(format_args!("alias: {arg}: not found"));
+                        code = 1;
+                    }
+                },
+            }
+        }
+        Ok(code)
+    }
+}
+
+impl BuiltIn for Unalias {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        let (options, names) = parse_options(args, "a")?;
+        if options.contains(&('a', true)) {
+            shell.aliases_mut().clear();
+            return Ok(0);
+        }
+        if names.is_empty() {
+            return Err(anyhow!("usage: unalias [-a] name [name ...]"));
+        }
+
+        let mut code = 0;
+        for name in names {
+            if shell.aliases_mut().remove(name).is_none() {
+                report(format_args!("unalias: {name}: not found"));
+                code = 1;
+            }
+        }
+        Ok(code)
+    }
+}
@@ -4,31 +4,41 @@ use super::BuiltIn;
 
 pub struct Cd {}
 
-fn set_new_cwd(dir: &str) -> anyhow::Result<()> {
+fn set_new_cwd(shell: &mut dyn Shell, dir: &str) -> anyhow::Result<()> {
     let cwd = std::env::current_dir()?;
-    std::env::set_var("OLDPWD", cwd);
     std::env::set_current_dir(dir)?;
+
+    let variables = shell.variables_mut();
+    variables.set("OLDPWD", cwd.to_string_lossy().into_owned())?;
+    variables.set_exported("OLDPWD", true);
+    let new_cwd = std::env::current_dir()?;
+    variables.set("PWD", new_cwd.to_string_lossy().into_owned())?;
+    variables.set_exported("PWD", true);
     Ok(())
 }
 
 impl BuiltIn for Cd {
-    fn call(&self, _: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
         if args.len() > 1 {
             return Err(anyhow::anyhow!("too many arguments"));
         }
         if args.is_empty() {
-            let home = std::env::var("HOME")?;
-            set_new_cwd(&home)?;
+            let home = shell
+                .get_var("HOME")
+                .ok_or_else(|| anyhow::anyhow!("HOME not set"))?;
+            set_new_cwd(shell, &home)?;
         } else {
             let path = args[0].clone();
 
             let new_cdw = if path == "-" {
-                std::env::var("OLDPWD")?
+                shell
+                    .get_var("OLDPWD")
+                    .ok_or_else(|| anyhow::anyhow!("OLDPWD not set"))?
             } else {
                 path
             };
 
-            set_new_cwd(&new_cdw)?;
+            set_new_cwd(shell, &new_cdw)?;
         }
         Ok(0)
     }
@@ -0,0 +1,75 @@
+use crate::{
+    error::report,
+    shell::{variables::Variable, Shell},
+};
+
+use super::{parse_assignment, parse_options, quote, BuiltIn};
+
+pub struct Declare {}
+
+fn print_variable(name: &str, variable: &Variable) {
+    match &variable.value {
+        Some(value) => println!("declare -{} {name}={}", variable.flags(), quote(value)),
+        None => println!("declare -{} {name}", variable.flags()),
+    }
+}
+
+impl BuiltIn for Declare {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        let (options, names) = parse_options(args, "pirx")?;
+        let print = options.contains(&('p', true));
+        let attributes: Vec<_> = options.into_iter().filter(|(o, _)| *o != 'p').collect();
+
+        if names.is_empty() {
+            for (name, variable) in shell.variables().iter() {
+                let matches = attributes.iter().all(|(option, _)| match option {
+                    'i' => variable.integer,
+                    'r' => variable.readonly,
+                    _ => variable.exported,
+                });
+                if matches {
+                    print_variable(name, variable);
+                }
+            }
+            return Ok(0);
+        }
+
+        if print {
+            let mut code = 0;
+            for name in names {
+                match shell.variables().get(name) {
+                    Some(variable) => print_variable(name, variable),
+                    None => {
+                        report(format_args!("declare: {name}: not found"));
+                        code = 1;
+                    }
+                }
+            }
+            return Ok(code);
+        }
+
+        for arg in names {
+            let (name, value) = parse_assignment(arg)?;
+            let variables = shell.variables_mut();
+            for (option, enabled) in &attributes {
+                if *option == 'i' {
+                    variables.set_integer(name, *enabled)?;
+                }
+            }
+            if let Some(value) = value {
+                variables.set(name, value.to_string())?;
+            } else if variables.get(name).is_none() {
+                variables.set_exported(name, false);
+            }
+            for (option, enabled) in &attributes {
+                match option {
+                    'x' => variables.set_exported(name, *enabled),
+                    'r' if *enabled => variables.set_readonly(name),
+                    'r' => return Err(anyhow::anyhow!("{name}: readonly variable")),
+                    _ => {}
+                }
+            }
+        }
+        Ok(0)
+    }
+}
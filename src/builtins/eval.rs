@@ -0,0 +1,24 @@
+use anyhow::anyhow;
+
+use crate::{
+    exec::execute_list,
+    parser::{is_blank, parse_command_with_aliases},
+    shell::Shell,
+};
+
+use super::BuiltIn;
+
+/// `eval` runs its arguments, joined with spaces, as a command.
+pub struct Eval {}
+
+impl BuiltIn for Eval {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        let command = args.join(" ");
+        if is_blank(&command) {
+            return Ok(0);
+        }
+        let list =
+            parse_command_with_aliases(&command, shell.aliases()).map_err(|e| anyhow!("{e}"))?;
+        Ok(execute_list(shell, &list))
+    }
+}
@@ -0,0 +1,34 @@
+use crate::shell::Shell;
+
+use super::{parse_assignment, parse_options, quote, BuiltIn};
+
+pub struct Export {}
+
+impl BuiltIn for Export {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        let (options, names) = parse_options(args, "np")?;
+        let unexport = options.contains(&('n', true));
+
+        if names.is_empty() {
+            for (name, variable) in shell.variables().iter() {
+                if !variable.exported {
+                    continue;
+                }
+                match &variable.value {
+                    Some(value) => println!("export {name}={}", quote(value)),
+                    None => println!("export {name}"),
+                }
+            }
+            return Ok(0);
+        }
+
+        for arg in names {
+            let (name, value) = parse_assignment(arg)?;
+            if let Some(value) = value {
+                shell.set_var(name, value.to_string())?;
+            }
+            shell.variables_mut().set_exported(name, !unexport);
+        }
+        Ok(0)
+    }
+}
@@ -0,0 +1,144 @@
+use anyhow::anyhow;
+
+use crate::{
+    error::report,
+    proc::{job::Job, Status},
+    shell::Shell,
+};
+
+use super::BuiltIn;
+
+pub struct Fg {}
+
+pub struct Bg {}
+
+/// The id of the job named by `spec`, the current job by default.
+fn job_id(shell: &dyn Shell, spec: Option<&String>) -> anyhow::Result<usize> {
+    shell.job_table().resolve(spec.map_or("%+", String::as_str))
+}
+
+fn get_job(shell: &mut dyn Shell, id: usize) -> anyhow::Result<&mut Job> {
+    shell
+        .job_table_mut()
+        .get_job_mut(id)
+        .ok_or_else(|| anyhow!("%{id}: no such job"))
+}
+
+impl BuiltIn for Fg {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        if !shell.job_control() {
+            return Err(anyhow!("no job control"));
+        }
+        if args.len() > 1 {
+            return Err(anyhow!("too many arguments"));
+        }
+        let id = job_id(shell, args.first())?;
+
+        shell.job_table_mut().make_current(id);
+        let job = get_job(shell, id)?;
+        println!("{}", job.name.trim_end());
+        job.resume(false, true)?;
+        let finished = job.last_status.is_finished();
+        let code = job.exit_status().map_or(0, |status| status.to_exit_code());
+        if finished {
+            shell.job_table_mut().remove_job(id)?;
+        }
+        Ok(code)
+    }
+}
+
+impl BuiltIn for Bg {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        if !shell.job_control() {
+            return Err(anyhow!("no job control"));
+        }
+        let ids = if args.is_empty() {
+            vec![job_id(shell, None)?]
+        } else {
+            args.iter()
+                .map(|arg| job_id(shell, Some(arg)))
+                .collect::<anyhow::Result<_>>()?
+        };
+
+        for id in ids {
+            shell.job_table_mut().make_current(id);
+            let job = get_job(shell, id)?;
+            if job.last_status == Status::Running {
+                report(format_args!("bg: job {id} already in background"));
+                continue;
+            }
+            job.resume(true, true)?;
+            println!("[{id}]\t{} &", job.name.trim_end());
+        }
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nix::{
+        sys::signal::{killpg, Signal},
+        unistd::Pid,
+    };
+
+    use crate::{shell::DefaultShell, test_util::run};
+
+    use super::*;
+
+    fn shell_with_job_control() -> DefaultShell {
+        let mut shell = DefaultShell::default();
+        shell.options_mut().monitor = true;
+        shell
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    /// Sends `signal` to job `id` and waits until its status is `status`.
+    fn signal_job(shell: &mut DefaultShell, id: usize, signal: Signal, status: Status) {
+        let job = shell.job_table_mut().get_job_mut(id).unwrap();
+        killpg(Pid::from_raw(job.pgid.0), signal).unwrap();
+        while job.last_status != status {
+            job.update(true).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_bg_stopped_job() {
+        let mut shell = shell_with_job_control();
+        run(&mut shell, "sleep 10 &");
+        signal_job(&mut shell, 1, Signal::SIGSTOP, Status::Stopped);
+
+        assert_eq!(Bg {}.call(&mut shell, &args(&["%1"])).unwrap(), 0);
+        let job = shell.job_table().get_job(1).unwrap();
+        assert_eq!(job.last_status, Status::Running);
+        assert!(job.background);
+        // A job running already is left as it is.
+        assert_eq!(Bg {}.call(&mut shell, &[]).unwrap(), 0);
+
+        signal_job(&mut shell, 1, Signal::SIGKILL, Status::Killed);
+    }
+
+    #[test]
+    fn test_fg_bg_errors() {
+        let mut shell = shell_with_job_control();
+        let error = Fg {}.call(&mut shell, &[]).unwrap_err();
+        assert_eq!(error.to_string(), "current: no such job");
+        let error = Bg {}.call(&mut shell, &[]).unwrap_err();
+        assert_eq!(error.to_string(), "current: no such job");
+
+        run(&mut shell, "sleep 10 &");
+        let error = Fg {}.call(&mut shell, &args(&["%2"])).unwrap_err();
+        assert_eq!(error.to_string(), "%2: no such job");
+        let error = Bg {}.call(&mut shell, &args(&["%ls"])).unwrap_err();
+        assert_eq!(error.to_string(), "%ls: no such job");
+        let error = Fg {}.call(&mut shell, &args(&["%1", "%1"])).unwrap_err();
+        assert_eq!(error.to_string(), "too many arguments");
+        signal_job(&mut shell, 1, Signal::SIGKILL, Status::Killed);
+
+        let mut shell = DefaultShell::default();
+        let error = Fg {}.call(&mut shell, &[]).unwrap_err();
+        assert_eq!(error.to_string(), "no job control");
+    }
+}
@@ -1,4 +1,4 @@
-use crate::shell::Shell;
+use crate::{error::report, shell::Shell};
 
 use super::BuiltIn;
 
@@ -6,10 +6,21 @@ pub struct Jobs {}
 
 impl BuiltIn for Jobs {
     fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
-        if !args.is_empty() {
-            return Err(anyhow::anyhow!("too many arguments"));
+        if args.is_empty() {
+            shell.print_jobs();
+            return Ok(0);
         }
-        shell.print_jobs();
-        Ok(0)
+
+        let mut code = 0;
+        for spec in args {
+            match shell.job_table().resolve(spec) {
+                Ok(id) => shell.job_table().print_job(id),
+                Err(e) => {
+                    report(format_args!("jobs: {e}"));
+                    code = 1;
+                }
+            }
+        }
+        Ok(code)
     }
 }
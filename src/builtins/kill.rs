@@ -1,43 +1,137 @@
 use std::str::FromStr;
 
-use nix::sys::signal::Signal::{self, SIGTERM};
+use anyhow::anyhow;
+use nix::{
+    sys::signal::{kill, killpg, Signal},
+    unistd::Pid,
+};
 
-use crate::shell::Shell;
+use crate::{error::report, shell::Shell};
 
 use super::BuiltIn;
 
 pub struct Kill {}
 
+/// Parses a signal given by number, or by name with or without its `SIG`
+/// prefix. Signal 0 is `None`, which only checks that the targets exist.
+fn parse_signal(spec: &str) -> anyhow::Result<Option<Signal>> {
+    let signal = match spec.parse::<i32>() {
+        Ok(0) => return Ok(None),
+        Ok(number) => Signal::try_from(number).ok(),
+        Err(_) => {
+            let name = spec.to_ascii_uppercase();
+            if name.starts_with("SIG") {
+                Signal::from_str(&name).ok()
+            } else {
+                Signal::from_str(&format!("SIG{name}")).ok()
+            }
+        }
+    };
+    signal
+        .map(Some)
+        .ok_or_else(|| anyhow!("{spec}: invalid signal specification"))
+}
+
+/// Splits the signal given as `-s NAME`, `-NAME` or `-N` from the operands,
+/// SIGTERM by default.
+fn parse_args(args: &[String]) -> anyhow::Result<(Option<Signal>, &[String])> {
+    let (signal, operands) = match args {
+        [option, spec, operands @ ..] if option == "-s" => (parse_signal(spec)?, operands),
+        [option] if option == "-s" => return Err(anyhow!("-s: option requires an argument")),
+        [option, operands @ ..]
+            if option.len() > 1 && option.starts_with('-') && option != "--" =>
+        {
+            (parse_signal(&option[1..])?, operands)
+        }
+        _ => (Some(Signal::SIGTERM), args),
+    };
+    let operands = match operands {
+        [separator, operands @ ..] if separator == "--" => operands,
+        _ => operands,
+    };
+    Ok((signal, operands))
+}
+
+/// Sends `signal` to a process, or to the process group of a job given as a
+/// job specification.
+fn send(shell: &dyn Shell, target: &str, signal: Option<Signal>) -> anyhow::Result<()> {
+    let result = if target.starts_with('%') {
+        let id = shell.job_table().resolve(target)?;
+        killpg(Pid::from_raw(shell.get_job_pgid(id)?), signal)
+    } else {
+        let pid = target
+            .parse::<i32>()
+            .map_err(|_| anyhow!("{target}: arguments must be process or job IDs"))?;
+        kill(Pid::from_raw(pid), signal)
+    };
+    result.map_err(|e| anyhow!("({target}) - {}", e.desc()))
+}
+
 impl BuiltIn for Kill {
     fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
-        if args.is_empty() {
-            return Err(anyhow::anyhow!("not enough arguments"));
-        }
-        if args.len() > 2 {
-            return Err(anyhow::anyhow!("too many arguments"));
+        let (signal, targets) = parse_args(args)?;
+        if targets.is_empty() {
+            return Err(anyhow!("not enough arguments"));
         }
 
-        let signal = if args.len() == 2 {
-            Signal::try_from(i32::from_str(&args[0][1..])?)?
-        } else {
-            SIGTERM
-        };
-
-        let pid_s = if args.len() == 2 {
-            args[1].clone()
-        } else {
-            args[0].clone()
-        };
-
-        if let Some(stripped) = pid_s.strip_prefix('%') {
-            let job_id = stripped.parse::<usize>()?;
-            let pgid = shell.get_job_pgid(job_id)?;
-            nix::sys::signal::killpg(nix::unistd::Pid::from_raw(pgid), signal)?;
-        } else {
-            let pid = pid_s.parse::<i32>()?;
-            nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), signal)?;
+        let mut code = 0;
+        for target in targets {
+            if let Err(e) = send(shell, target, signal) {
+                report(format_args!("kill: {e}"));
+                code = 1;
+            }
         }
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{shell::DefaultShell, test_util::run};
+
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_signals() {
+        assert_eq!(parse_signal("9").unwrap(), Some(Signal::SIGKILL));
+        assert_eq!(parse_signal("INT").unwrap(), Some(Signal::SIGINT));
+        assert_eq!(parse_signal("sigstop").unwrap(), Some(Signal::SIGSTOP));
+        assert_eq!(parse_signal("0").unwrap(), None);
+        let error = parse_signal("FOO").unwrap_err();
+        assert_eq!(error.to_string(), "FOO: invalid signal specification");
+
+        let args = args(&["-s", "HUP", "--", "-1", "2"]);
+        let (signal, operands) = parse_args(&args).unwrap();
+        assert_eq!(signal, Some(Signal::SIGHUP));
+        assert_eq!(operands, ["-1", "2"]);
+        assert!(parse_args(&args[..1]).is_err());
+        let (signal, operands) = parse_args(&args[2..]).unwrap();
+        assert_eq!(signal, Some(Signal::SIGTERM));
+        assert_eq!(operands, ["-1", "2"]);
+    }
+
+    #[test]
+    fn test_kill_several_targets() {
+        let mut shell = DefaultShell::default();
+        run(&mut shell, "sleep 10 & sleep 10 &");
+        let pid = shell.last_background_pid().unwrap();
+        assert_eq!(run(&mut shell, &format!("kill -KILL %1 {pid}")), 0);
+        assert_eq!(run(&mut shell, "wait %1"), 137);
+        assert_eq!(run(&mut shell, "wait %2"), 137);
 
-        Ok(0)
+        run(&mut shell, "sleep 10 &");
+        // The other targets get the signal even if one of them is wrong.
+        let error = send(&shell, "foo", None).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "foo: arguments must be process or job IDs"
+        );
+        assert_eq!(run(&mut shell, "kill -s INT foo %1"), 1);
+        assert_eq!(run(&mut shell, "wait %1"), 130);
+        assert_eq!(run(&mut shell, "kill"), 1);
     }
 }
@@ -0,0 +1,20 @@
+use crate::shell::Shell;
+
+use super::{declare::Declare, parse_assignment, parse_options, BuiltIn};
+
+pub struct Local {}
+
+impl BuiltIn for Local {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        let (_, names) = parse_options(args, "irx")?;
+        for arg in names {
+            let (name, _) = parse_assignment(arg)?;
+            shell.variables_mut().make_local(name)?;
+        }
+        if names.is_empty() {
+            return Ok(0);
+        }
+        // The attributes and values are then set as `declare` does.
+        Declare {}.call(shell, args)
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::anyhow;
+
+use crate::{
+    error::report,
+    shell::{ControlFlow, Shell},
+};
+
+use super::BuiltIn;
+
+pub struct Break {}
+
+pub struct Continue {}
+
+impl BuiltIn for Break {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        leave_loops(shell, "break", args, ControlFlow::Break)
+    }
+}
+
+impl BuiltIn for Continue {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        leave_loops(shell, "continue", args, ControlFlow::Continue)
+    }
+}
+
+/// Requests `control_flow` for the number of enclosing loops given in
+/// `args`, 1 by default and at most the number of loops there are.
+fn leave_loops(
+    shell: &mut dyn Shell,
+    name: &str,
+    args: &[String],
+    control_flow: fn(usize) -> ControlFlow,
+) -> anyhow::Result<i32> {
+    let levels = match args {
+        [] => 1,
+        [levels] => match levels.parse::<i64>() {
+            Ok(levels) if levels >= 1 => usize::try_from(levels).unwrap_or(usize::MAX),
+            Ok(_) => return Err(anyhow!("{name}: {levels}: loop count out of range")),
+            Err(_) => return Err(anyhow!("{name}: {levels}: numeric argument required")),
+        },
+        _ => return Err(anyhow!("{name}: too many arguments")),
+    };
+
+    let depth = shell.loop_depth();
+    if depth == 0 {
+        report(format_args!(
+            "{name}: only meaningful in a `for', `while', or `until' loop"
+        ));
+        return Ok(0);
+    }
+    shell.set_control_flow(Some(control_flow(levels.min(depth))));
+    Ok(0)
+}
@@ -1,25 +1,158 @@
-use crate::shell::Shell;
+use anyhow::anyhow;
 
+use crate::{expansion::is_name, shell::Shell};
+
+use self::alias::{Alias, Unalias};
 use self::cd::Cd;
+use self::colon::Colon;
+use self::declare::Declare;
+use self::eval::Eval;
 use self::exit::Exit;
+use self::export::Export;
+use self::fg_bg::{Bg, Fg};
 use self::jobs::Jobs;
 use self::kill::Kill;
+use self::local::Local;
+use self::loop_control::{Break, Continue};
+use self::r#return::Return;
+use self::readonly::Readonly;
+use self::set::Set;
+use self::shift::Shift;
+use self::shopt::Shopt;
+use self::source::Source;
+use self::unset::Unset;
+use self::wait::Wait;
 
+mod alias;
 mod cd;
+mod colon;
+mod declare;
+mod eval;
 mod exit;
+mod export;
+mod fg_bg;
 mod jobs;
 mod kill;
+mod local;
+mod loop_control;
+mod readonly;
+mod r#return;
+mod set;
+mod shift;
+mod shopt;
+mod source;
+mod unset;
+mod wait;
 
 pub trait BuiltIn {
     fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32>;
 }
 
-pub fn get_builtin(command: &crate::parser::ast::Command) -> Option<Box<dyn BuiltIn>> {
+pub fn get_builtin(command: &crate::parser::ast::SimpleCommand) -> Option<Box<dyn BuiltIn>> {
     match command.name.as_str() {
+        "." | "source" => Some(Box::new(Source {})),
+        ":" => Some(Box::new(Colon {})),
+        "alias" => Some(Box::new(Alias {})),
+        "bg" => Some(Box::new(Bg {})),
+        "break" => Some(Box::new(Break {})),
         "cd" => Some(Box::new(Cd {})),
+        "continue" => Some(Box::new(Continue {})),
+        "declare" => Some(Box::new(Declare {})),
+        "eval" => Some(Box::new(Eval {})),
         "exit" => Some(Box::new(Exit {})),
+        "export" => Some(Box::new(Export {})),
+        "fg" => Some(Box::new(Fg {})),
         "jobs" => Some(Box::new(Jobs {})),
         "kill" => Some(Box::new(Kill {})),
+        "local" => Some(Box::new(Local {})),
+        "readonly" => Some(Box::new(Readonly {})),
+        "return" => Some(Box::new(Return {})),
+        "set" => Some(Box::new(Set {})),
+        "shift" => Some(Box::new(Shift {})),
+        "shopt" => Some(Box::new(Shopt {})),
+        "unalias" => Some(Box::new(Unalias {})),
+        "unset" => Some(Box::new(Unset {})),
+        "wait" => Some(Box::new(Wait {})),
         _ => None,
     }
 }
+
+/// Whether a builtin is one of the special builtins of POSIX, after which
+/// the assignments prefixing them stay in effect. Only the ones the shell
+/// has are listed.
+pub fn is_special_builtin(name: &str) -> bool {
+    matches!(
+        name,
+        "break"
+            | ":"
+            | "."
+            | "continue"
+            | "eval"
+            | "exit"
+            | "export"
+            | "readonly"
+            | "return"
+            | "set"
+            | "shift"
+            | "unset"
+    )
+}
+
+/// An option of a builtin, with `true` if it was given with `-` rather
+/// than `+`.
+type BuiltInOption = (char, bool);
+
+/// Splits the leading options of a builtin, such as `-ab` or `+x`, from its
+/// operands.
+fn parse_options<'a>(
+    args: &'a [String],
+    allowed: &str,
+) -> anyhow::Result<(Vec<BuiltInOption>, &'a [String])> {
+    let mut options = Vec::new();
+    let mut i = 0;
+
+    while let Some(arg) = args.get(i) {
+        if arg == "--" {
+            i += 1;
+            break;
+        }
+        let enabled = arg.starts_with('-');
+        if arg.len() < 2 || !(enabled || arg.starts_with('+')) {
+            break;
+        }
+        for option in arg[1..].chars() {
+            if !allowed.contains(option) {
+                return Err(anyhow!("{}{option}: invalid option", &arg[..1]));
+            }
+            options.push((option, enabled));
+        }
+        i += 1;
+    }
+
+    Ok((options, &args[i..]))
+}
+
+/// Splits a `NAME=value` operand, checking that the name is valid.
+fn parse_assignment(arg: &str) -> anyhow::Result<(&str, Option<&str>)> {
+    let (name, value) = match arg.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (arg, None),
+    };
+    if !is_name(name) {
+        return Err(anyhow!("`{arg}': not a valid identifier"));
+    }
+    Ok((name, value))
+}
+
+/// Quotes a value so that it can be read back by the shell.
+pub fn quote(value: &str) -> String {
+    let safe = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./:@%+,".contains(c));
+    if safe {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
@@ -0,0 +1,33 @@
+use crate::shell::Shell;
+
+use super::{parse_assignment, parse_options, quote, BuiltIn};
+
+pub struct Readonly {}
+
+impl BuiltIn for Readonly {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        let (_, names) = parse_options(args, "p")?;
+
+        if names.is_empty() {
+            for (name, variable) in shell.variables().iter() {
+                if !variable.readonly {
+                    continue;
+                }
+                match &variable.value {
+                    Some(value) => println!("readonly {name}={}", quote(value)),
+                    None => println!("readonly {name}"),
+                }
+            }
+            return Ok(0);
+        }
+
+        for arg in names {
+            let (name, value) = parse_assignment(arg)?;
+            if let Some(value) = value {
+                shell.set_var(name, value.to_string())?;
+            }
+            shell.variables_mut().set_readonly(name);
+        }
+        Ok(0)
+    }
+}
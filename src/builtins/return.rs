@@ -0,0 +1,28 @@
+use anyhow::anyhow;
+
+use crate::shell::{ControlFlow, Shell};
+
+use super::BuiltIn;
+
+pub struct Return {}
+
+impl BuiltIn for Return {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        if args.len() > 1 {
+            return Err(anyhow!("too many arguments"));
+        }
+        if shell.frame_depth() == 0 && shell.source_depth() == 0 {
+            return Err(anyhow!(
+                "can only `return' from a function or sourced script"
+            ));
+        }
+        let exit_code = match args.first() {
+            Some(code) => code
+                .parse::<i32>()
+                .map_err(|_| anyhow!("{code}: numeric argument required"))?,
+            None => shell.last_exit_code(),
+        };
+        shell.set_control_flow(Some(ControlFlow::Return));
+        Ok(exit_code)
+    }
+}
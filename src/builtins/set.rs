@@ -0,0 +1,68 @@
+use anyhow::anyhow;
+
+use crate::shell::{options::ShellOptions, Shell};
+
+use super::{quote, BuiltIn};
+
+pub struct Set {}
+
+impl BuiltIn for Set {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        if args.is_empty() {
+            for (name, variable) in shell.variables().iter() {
+                if let Some(value) = &variable.value {
+                    println!("{name}={}", quote(value));
+                }
+            }
+            return Ok(0);
+        }
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                shell.set_positional_parameters(args.cloned().collect());
+                return Ok(0);
+            }
+            let enabled = arg.starts_with('-');
+            if arg.len() < 2 || !(enabled || arg.starts_with('+')) {
+                // The first operand starts the positional parameters.
+                let mut parameters = vec![arg.clone()];
+                parameters.extend(args.cloned());
+                shell.set_positional_parameters(parameters);
+                return Ok(0);
+            }
+
+            for flag in arg[1..].chars() {
+                match flag {
+                    'C' => shell.options_mut().noclobber = enabled,
+                    'o' => match args.next() {
+                        Some(name) => set_option(shell, name, enabled)?,
+                        None => print_options(shell, enabled),
+                    },
+                    _ => return Err(anyhow!("{}{flag}: invalid option", &arg[..1])),
+                }
+            }
+        }
+        Ok(0)
+    }
+}
+
+fn set_option(shell: &mut dyn Shell, name: &str, value: bool) -> anyhow::Result<()> {
+    if !ShellOptions::SET_NAMES.contains(&name) {
+        return Err(anyhow!("{name}: invalid option name"));
+    }
+    shell.options_mut().set(name, value)
+}
+
+/// Lists the options, as a table for `set -o` and as commands restoring
+/// them for `set +o`.
+fn print_options(shell: &dyn Shell, table: bool) {
+    for name in ShellOptions::SET_NAMES {
+        let value = shell.options().get(name).unwrap_or_default();
+        if table {
+            println!("{name:<15}\t{}", if value { "on" } else { "off" });
+        } else {
+            println!("set {}o {name}", if value { '-' } else { '+' });
+        }
+    }
+}
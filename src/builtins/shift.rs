@@ -0,0 +1,27 @@
+use anyhow::anyhow;
+
+use crate::shell::Shell;
+
+use super::BuiltIn;
+
+pub struct Shift {}
+
+impl BuiltIn for Shift {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        let count = match args {
+            [] => 1,
+            [count] => count
+                .parse::<usize>()
+                .map_err(|_| anyhow!("{count}: numeric argument required"))?,
+            _ => return Err(anyhow!("too many arguments")),
+        };
+
+        let parameters = shell.positional_parameters();
+        if count > parameters.len() {
+            return Err(anyhow!("{count}: shift count out of range"));
+        }
+        let parameters = parameters[count..].to_vec();
+        shell.set_positional_parameters(parameters);
+        Ok(0)
+    }
+}
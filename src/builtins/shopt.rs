@@ -0,0 +1,69 @@
+use crate::shell::{options::ShellOptions, Shell};
+
+use super::{parse_options, BuiltIn};
+
+pub struct Shopt {}
+
+impl BuiltIn for Shopt {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        let (options, names) = parse_options(args, "supq")?;
+        let has = |option| options.contains(&(option, true));
+        if has('s') && has('u') {
+            return Err(anyhow::anyhow!(
+                "cannot set and unset options simultaneously"
+            ));
+        }
+
+        if let Some(name) = names
+            .iter()
+            .find(|name| !ShellOptions::SHOPT_NAMES.contains(&name.as_str()))
+        {
+            return Err(anyhow::anyhow!("{name}: invalid shell option name"));
+        }
+
+        let listing = names.is_empty();
+        let names: Vec<&str> = if listing {
+            ShellOptions::SHOPT_NAMES.to_vec()
+        } else {
+            names.iter().map(String::as_str).collect()
+        };
+
+        if (has('s') || has('u')) && listing {
+            // Without names, -s and -u list the options that are set or unset.
+            for name in names {
+                let value = shell.options().get(name)?;
+                if value == has('s') {
+                    print_option(name, value, has('p'));
+                }
+            }
+            return Ok(0);
+        }
+
+        if has('s') || has('u') {
+            for name in names {
+                shell.options_mut().set(name, has('s'))?;
+            }
+            return Ok(0);
+        }
+
+        let mut code = 0;
+        for name in names {
+            let value = shell.options().get(name)?;
+            if !value {
+                code = 1;
+            }
+            if !has('q') {
+                print_option(name, value, has('p'));
+            }
+        }
+        Ok(code)
+    }
+}
+
+fn print_option(name: &str, value: bool, reusable: bool) {
+    if reusable {
+        println!("shopt {} {name}", if value { "-s" } else { "-u" });
+    } else {
+        println!("{name:<15}\t{}", if value { "on" } else { "off" });
+    }
+}
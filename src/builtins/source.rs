@@ -0,0 +1,92 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+
+use crate::{
+    exec::search_path,
+    script::run_file,
+    shell::{ControlFlow, Shell},
+};
+
+use super::BuiltIn;
+
+pub struct Source {}
+
+/// Finds the file read by `source`, names without a slash are looked up in
+/// `$PATH` before the current directory.
+fn find_file(shell: &dyn Shell, name: &str) -> PathBuf {
+    if !name.contains('/') {
+        let path = shell.get_var("PATH").unwrap_or_default();
+        if let Some(file) = search_path(name, &path, Path::is_file) {
+            return file;
+        }
+    }
+    PathBuf::from(name)
+}
+
+impl BuiltIn for Source {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        let (name, parameters) = args
+            .split_first()
+            .ok_or_else(|| anyhow!("filename argument required"))?;
+        let file = find_file(shell, name);
+
+        // The arguments replace the positional parameters while the file runs.
+        let saved = (!parameters.is_empty()).then(|| {
+            let saved = shell.positional_parameters().to_vec();
+            shell.set_positional_parameters(parameters.to_vec());
+            saved
+        });
+        let depth = shell.source_depth();
+        shell.set_source_depth(depth + 1);
+
+        let result = run_file(shell, &file);
+
+        shell.set_source_depth(depth);
+        if shell.control_flow() == Some(ControlFlow::Return) {
+            shell.set_control_flow(None);
+        }
+        if let Some(saved) = saved {
+            shell.set_positional_parameters(saved);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shell::DefaultShell;
+
+    use super::*;
+
+    fn strings(strings: &[&str]) -> Vec<String> {
+        strings.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_source_from_path() {
+        let dir = std::env::temp_dir().join(format!("rjsh-source-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("lib.sh"), "sourced=$1\n").unwrap();
+
+        let mut shell = DefaultShell::default();
+        let path = format!("/rjsh/missing:{}", dir.display());
+        shell.set_var("PATH", path).unwrap();
+        assert_eq!(find_file(&shell, "lib.sh"), dir.join("lib.sh"));
+        assert_eq!(find_file(&shell, "missing.sh"), PathBuf::from("missing.sh"));
+        assert_eq!(find_file(&shell, "./lib.sh"), PathBuf::from("./lib.sh"));
+
+        // The arguments are the positional parameters while the file runs.
+        shell.set_positional_parameters(strings(&["a"]));
+        let code = Source {}.call(&mut shell, &strings(&["lib.sh", "x", "y"]));
+        assert_eq!(code.unwrap(), 0);
+        assert_eq!(shell.get_var("sourced").as_deref(), Some("x"));
+        assert_eq!(shell.positional_parameters(), ["a"]);
+
+        // Without arguments, the file sees the ones of the shell.
+        Source {}.call(&mut shell, &strings(&["lib.sh"])).unwrap();
+        assert_eq!(shell.get_var("sourced").as_deref(), Some("a"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
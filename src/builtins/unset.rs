@@ -0,0 +1,31 @@
+use crate::{expansion::is_name, shell::Shell};
+
+use super::{parse_options, BuiltIn};
+
+pub struct Unset {}
+
+impl BuiltIn for Unset {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        let (options, names) = parse_options(args, "fv")?;
+        let functions = options.contains(&('f', true));
+        let variables = options.contains(&('v', true));
+
+        for name in names {
+            if functions {
+                shell.unset_function(name);
+                continue;
+            }
+            if !is_name(name) {
+                return Err(anyhow::anyhow!("`{name}': not a valid identifier"));
+            }
+            // Without options, functions are only unset when there is no
+            // variable of that name.
+            if variables || shell.variables().get(name).is_some() {
+                shell.variables_mut().unset(name)?;
+            } else {
+                shell.unset_function(name);
+            }
+        }
+        Ok(0)
+    }
+}
@@ -0,0 +1,324 @@
+use anyhow::anyhow;
+use nix::{
+    errno::Errno,
+    sys::{
+        signal::Signal,
+        wait::{waitid, Id, WaitPidFlag},
+    },
+};
+
+use crate::{error::report, proc::Status, shell::Shell};
+
+use super::{parse_options, BuiltIn};
+
+pub struct Wait {}
+
+/// The exit status of `wait` for pids that are not children of the shell.
+const UNKNOWN_PID: i32 = 127;
+
+/// The exit status of `wait` when SIGINT interrupts it, which it does under
+/// job control.
+const INTERRUPTED: i32 = 128 + Signal::SIGINT as i32;
+
+fn is_interrupted(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<Errno>() == Some(&Errno::EINTR)
+}
+
+/// What an operand of `wait` names.
+enum Target {
+    /// A job, with the pid it was named by if it was not a job
+    /// specification.
+    Job { id: usize, pid: Option<i32> },
+    /// A process of a job removed from the job table once done.
+    Finished { pid: i32, code: i32 },
+}
+
+/// Finds the job named by a pid or a job specification.
+fn find_target(shell: &mut dyn Shell, arg: &str) -> anyhow::Result<Option<Target>> {
+    if arg.starts_with('%') {
+        return match shell.job_table().resolve(arg) {
+            Ok(id) => Ok(Some(Target::Job { id, pid: None })),
+            Err(e) => {
+                report(format_args!("wait: {e}"));
+                Ok(None)
+            }
+        };
+    }
+    let pid = arg
+        .parse::<i32>()
+        .map_err(|_| anyhow!("`{arg}': not a pid or valid job spec"))?;
+    if let Some(id) = shell.job_table().find_pid(pid) {
+        return Ok(Some(Target::Job { id, pid: Some(pid) }));
+    }
+    match shell.job_table_mut().take_finished(pid) {
+        Some(code) => Ok(Some(Target::Finished { pid, code })),
+        None => {
+            report(format_args!("wait: pid {pid} is not a child of this shell"));
+            Ok(None)
+        }
+    }
+}
+
+/// Whether waiting for a job is over: it is done, or stopped unless `force`
+/// asks to wait for it to terminate.
+fn is_over(status: Status, force: bool) -> bool {
+    status.is_finished() || (!force && status == Status::Stopped)
+}
+
+/// Waits for a job, and returns the exit status of the process `pid` of it,
+/// or of the job itself. Finished jobs are removed from the job table.
+fn wait_for(shell: &mut dyn Shell, target: &Target, force: bool) -> anyhow::Result<i32> {
+    let (id, pid) = match *target {
+        Target::Job { id, pid } => (id, pid),
+        Target::Finished { code, .. } => return Ok(code),
+    };
+    // The job is gone if it was already waited for.
+    let Some(job) = shell.job_table_mut().get_job_mut(id) else {
+        return Ok(UNKNOWN_PID);
+    };
+    while !is_over(job.last_status, force) {
+        job.update(true)?;
+    }
+
+    let status = match pid {
+        Some(pid) => job
+            .processes
+            .iter()
+            .find(|process| process.pid().0 == pid)
+            .and_then(|process| process.exit_status()),
+        None => job.exit_status(),
+    };
+    if job.last_status.is_finished() {
+        shell.job_table_mut().remove_job(id)?;
+    }
+    Ok(status.map_or(0, |status| status.to_exit_code()))
+}
+
+/// Waits for the first of the jobs `ids` to be over, and returns its id.
+fn wait_next(shell: &mut dyn Shell, ids: &[usize], force: bool) -> anyhow::Result<usize> {
+    loop {
+        // All the jobs are updated, so that the children reported by
+        // `waitid` below are reaped.
+        for id in shell.job_table().ids() {
+            if let Some(job) = shell.job_table_mut().get_job_mut(id) {
+                job.update(false)?;
+            }
+        }
+        let over = ids.iter().copied().find(|&id| {
+            shell
+                .job_table()
+                .get_job(id)
+                .is_some_and(|job| is_over(job.last_status, force))
+        });
+        if let Some(id) = over {
+            return Ok(id);
+        }
+
+        // Blocks until a child changes status, without reaping it.
+        let flags = WaitPidFlag::WEXITED | WaitPidFlag::WSTOPPED | WaitPidFlag::WNOWAIT;
+        waitid(Id::All, flags)?;
+    }
+}
+
+/// Sets the variable given with `-p` to the pid a job was waited for by, or
+/// the one of the last process of the job.
+fn report_pid(
+    shell: &mut dyn Shell,
+    variable: Option<&str>,
+    target: &Target,
+) -> anyhow::Result<()> {
+    let Some(variable) = variable else {
+        return Ok(());
+    };
+    let pid = match *target {
+        Target::Job { pid: Some(pid), .. } | Target::Finished { pid, .. } => Some(pid),
+        Target::Job { id, pid: None } => shell
+            .job_table()
+            .get_job(id)
+            .and_then(|job| Some(job.processes.last()?.pid().0)),
+    };
+    shell.set_var(variable, pid.unwrap_or_default().to_string())
+}
+
+/// Waits for the first of the jobs named by the operands to be over, or of
+/// all the jobs without operands.
+fn wait_any(
+    shell: &mut dyn Shell,
+    operands: &[String],
+    force: bool,
+    variable: Option<&str>,
+) -> anyhow::Result<i32> {
+    let mut targets = Vec::new();
+    for arg in operands {
+        targets.extend(find_target(shell, arg)?);
+    }
+
+    let target = match targets
+        .iter()
+        .position(|target| matches!(target, Target::Finished { .. }))
+    {
+        Some(index) => targets.swap_remove(index),
+        None => {
+            let ids: Vec<usize> = if operands.is_empty() {
+                shell.job_table().ids()
+            } else {
+                targets
+                    .iter()
+                    .filter_map(|target| match *target {
+                        Target::Job { id, .. } => Some(id),
+                        Target::Finished { .. } => None,
+                    })
+                    .collect()
+            };
+            if ids.is_empty() {
+                return Ok(UNKNOWN_PID);
+            }
+            let id = wait_next(shell, &ids, force)?;
+            targets
+                .into_iter()
+                .find(|target| matches!(*target, Target::Job { id: job, .. } if job == id))
+                .unwrap_or(Target::Job { id, pid: None })
+        }
+    };
+    report_pid(shell, variable, &target)?;
+    wait_for(shell, &target, force)
+}
+
+/// Waits for the jobs named by the operands, or for all of them.
+fn wait(
+    shell: &mut dyn Shell,
+    operands: &[String],
+    any: bool,
+    force: bool,
+    variable: Option<&str>,
+) -> anyhow::Result<i32> {
+    if any {
+        return wait_any(shell, operands, force, variable);
+    }
+
+    if operands.is_empty() {
+        for id in shell.job_table().ids() {
+            wait_for(shell, &Target::Job { id, pid: None }, force)?;
+        }
+        shell.job_table_mut().update()?;
+        return Ok(0);
+    }
+
+    // The exit status is the one of the last operand.
+    let mut code = 0;
+    for arg in operands {
+        code = match find_target(shell, arg)? {
+            Some(target) => {
+                report_pid(shell, variable, &target)?;
+                wait_for(shell, &target, force)?
+            }
+            None => UNKNOWN_PID,
+        };
+    }
+    Ok(code)
+}
+
+impl BuiltIn for Wait {
+    fn call(&self, shell: &mut dyn Shell, args: &[String]) -> anyhow::Result<i32> {
+        let (options, operands) = parse_options(args, "fnp")?;
+        let any = options.contains(&('n', true));
+        let force = options.contains(&('f', true));
+        let (variable, operands) = if options.contains(&('p', true)) {
+            let (variable, operands) = operands
+                .split_first()
+                .ok_or_else(|| anyhow!("-p: option requires an argument"))?;
+            shell.variables_mut().unset(variable)?;
+            (Some(variable.as_str()), operands)
+        } else {
+            (None, operands)
+        };
+
+        match wait(shell, operands, any, force, variable) {
+            Err(e) if is_interrupted(&e) => Ok(INTERRUPTED),
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        shell::DefaultShell,
+        test_util::{catching_interrupts, run},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_wait_pid() {
+        let mut shell = DefaultShell::default();
+        assert_eq!(run(&mut shell, "sh -c 'exit 3' & wait $!"), 3);
+        assert_eq!(run(&mut shell, "sh -c 'exit 4' & sleep 0.1; wait $!"), 4);
+        // The status is only reported once.
+        assert_eq!(run(&mut shell, "wait $!"), UNKNOWN_PID);
+        assert_eq!(run(&mut shell, "sh -c 'exit 5' & wait %1"), 5);
+        assert_eq!(shell.job_table().size(), 0);
+    }
+
+    #[test]
+    fn test_wait_finished_job() {
+        let mut shell = DefaultShell::default();
+        run(&mut shell, "sh -c 'sleep 0.1; exit 6' &");
+        let pid = shell.last_background_pid().unwrap();
+        while shell.job_table().size() > 0 {
+            shell.job_table_mut().update().unwrap();
+        }
+        assert_eq!(run(&mut shell, &format!("wait {pid}")), 6);
+        assert_eq!(run(&mut shell, &format!("wait {pid}")), UNKNOWN_PID);
+    }
+
+    #[test]
+    fn test_wait_unknown() {
+        let mut shell = DefaultShell::default();
+        assert_eq!(run(&mut shell, "wait 1"), UNKNOWN_PID);
+        assert_eq!(run(&mut shell, "wait %1"), UNKNOWN_PID);
+        assert_eq!(run(&mut shell, "wait -n"), UNKNOWN_PID);
+        assert_eq!(run(&mut shell, "wait"), 0);
+        assert_eq!(run(&mut shell, "wait abc"), 1);
+    }
+
+    #[test]
+    fn test_wait_all() {
+        let mut shell = DefaultShell::default();
+        assert_eq!(run(&mut shell, "sh -c 'exit 2' & sleep 0.1 & wait"), 0);
+        assert_eq!(shell.job_table().size(), 0);
+    }
+
+    #[test]
+    fn test_wait_next() {
+        let mut shell = DefaultShell::default();
+        run(
+            &mut shell,
+            "sleep 0.3 & sh -c 'exit 7' & sh -c 'sleep 0.1; exit 8' &",
+        );
+        let pids: Vec<String> = shell
+            .job_table()
+            .ids()
+            .into_iter()
+            .map(|id| {
+                let job = shell.job_table().get_job(id).unwrap();
+                job.processes[0].pid().0.to_string()
+            })
+            .collect();
+
+        assert_eq!(run(&mut shell, "wait -n -p first"), 7);
+        assert_eq!(shell.get_var("first"), Some(pids[1].clone()));
+        assert_eq!(run(&mut shell, &format!("wait -n -p next {}", pids[2])), 8);
+        assert_eq!(shell.get_var("next"), Some(pids[2].clone()));
+        assert_eq!(run(&mut shell, "wait -n"), 0);
+        assert_eq!(run(&mut shell, "wait -n -p last"), UNKNOWN_PID);
+        assert_eq!(shell.get_var("last"), None);
+    }
+
+    #[test]
+    fn test_wait_interrupted() {
+        catching_interrupts(|shell| {
+            run(shell, "sleep 2 & (sleep 0.1; kill -2 $$) & wait %1") == INTERRUPTED
+        });
+    }
+}
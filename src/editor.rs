@@ -10,6 +10,8 @@ use rustyline::{
     CompletionType, Config, Editor, Helper,
 };
 
+use crate::parser::needs_more_input;
+
 struct RjshEditorHelper(FilenameCompleter);
 
 impl Completer for RjshEditorHelper {
@@ -70,6 +72,28 @@ impl RjshEditor {
         self.internal.readline(prompt)
     }
 
+    /// Reads a command, which can span several lines when it is incomplete,
+    /// such as a here-document. The following lines are read with
+    /// `continuation_prompt`, and an end of file ends the command.
+    pub fn read_command(
+        &mut self,
+        prompt: &str,
+        continuation_prompt: &str,
+    ) -> Result<String, ReadlineError> {
+        let mut command = self.readline(prompt)?;
+        while needs_more_input(&command) {
+            match self.readline(continuation_prompt) {
+                Ok(line) => {
+                    command.push('\n');
+                    command.push_str(&line);
+                }
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(command)
+    }
+
     pub fn load_history<P: AsRef<Path> + ?Sized>(&mut self, path: &P) -> Result<(), ReadlineError> {
         self.internal.load_history(path)
     }
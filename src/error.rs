@@ -1,3 +1,26 @@
+use std::{cell::RefCell, fmt::Display, io::Write};
+
+thread_local! {
+    /// The script and line of the command being run, which diagnostics are
+    /// reported at.
+    static LOCATION: RefCell<Option<(String, usize)>> = const { RefCell::new(None) };
+}
+
+/// Sets the location diagnostics are reported at, `None` outside of scripts,
+/// and returns the previous one.
+pub fn set_location(location: Option<(String, usize)>) -> Option<(String, usize)> {
+    LOCATION.with(|current| current.replace(location))
+}
+
+/// Prints a diagnostic of the shell, with the script and line it comes from.
+pub fn report(message: impl Display) {
+    let mut stderr = std::io::stderr();
+    let _ = LOCATION.with(|location| match &*location.borrow() {
+        Some((name, line)) => writeln!(stderr, "rjsh: {name}:{line}: {message}"),
+        None => writeln!(stderr, "rjsh: {message}"),
+    });
+}
+
 pub trait UnwrapPrintError {
     type T;
     fn unwrap_error_with_print(self) -> Self::T;
@@ -9,7 +32,7 @@ impl UnwrapPrintError for Result<i32, anyhow::Error> {
         match self {
             Ok(value) => value,
             Err(error) => {
-                eprintln!("rjsh: {error}");
+                report(error);
                 1
             }
         }
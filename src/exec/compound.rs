@@ -0,0 +1,180 @@
+use crate::{
+    error::UnwrapPrintError,
+    expansion::{expand_arithmetic, expand_pattern, expand_word, expand_words},
+    parser::ast::{CaseItem, CaseTerminator, CompoundCommand, List},
+    proc::terminal,
+    shell::{ControlFlow, Shell},
+};
+
+use super::{execute_list, with_expansions};
+
+/// Evaluates a compound command in the shell itself and returns its exit
+/// code. Subshells are expected to be forked already.
+pub fn execute_compound(shell: &mut dyn Shell, command: &CompoundCommand) -> i32 {
+    match command {
+        CompoundCommand::BraceGroup(list) | CompoundCommand::Subshell(list) => {
+            execute_list(shell, list)
+        }
+        CompoundCommand::Arithmetic(expression) => expand_arithmetic(shell, expression)
+            .map(|value| i32::from(value == 0))
+            .unwrap_error_with_print(),
+        CompoundCommand::If {
+            branches,
+            else_body,
+        } => execute_if(shell, branches, else_body.as_ref()),
+        CompoundCommand::While { condition, body } => {
+            in_loop(shell, |shell| execute_while(shell, condition, body, true))
+        }
+        CompoundCommand::Until { condition, body } => {
+            in_loop(shell, |shell| execute_while(shell, condition, body, false))
+        }
+        CompoundCommand::For { name, words, body } => in_loop(shell, |shell| {
+            execute_for(shell, name, words.as_deref(), body).unwrap_error_with_print()
+        }),
+        CompoundCommand::Case { word, items } => {
+            execute_case(shell, word, items).unwrap_error_with_print()
+        }
+    }
+}
+
+/// Whether the commands being run have to stop, because of `exit`, `break`,
+/// `continue`, `return` or SIGINT.
+fn interrupted(shell: &dyn Shell) -> bool {
+    shell.should_exit() || shell.control_flow().is_some() || terminal::interrupted()
+}
+
+fn execute_if(shell: &mut dyn Shell, branches: &[(List, List)], else_body: Option<&List>) -> i32 {
+    for (condition, body) in branches {
+        let code = execute_list(shell, condition);
+        if interrupted(shell) {
+            return code;
+        }
+        if code == 0 {
+            return execute_list(shell, body);
+        }
+    }
+    else_body.map_or(0, |body| execute_list(shell, body))
+}
+
+fn in_loop<F>(shell: &mut dyn Shell, f: F) -> i32
+where
+    F: FnOnce(&mut dyn Shell) -> i32,
+{
+    let depth = shell.loop_depth();
+    shell.set_loop_depth(depth + 1);
+    let code = f(shell);
+    shell.set_loop_depth(depth);
+    code
+}
+
+/// Consumes the `break` or `continue` aimed at the current loop, and returns
+/// whether the loop has to stop.
+fn loop_should_stop(shell: &mut dyn Shell) -> bool {
+    if shell.should_exit() || terminal::interrupted() {
+        return true;
+    }
+    match shell.control_flow() {
+        None => false,
+        Some(ControlFlow::Break(levels)) => {
+            shell.set_control_flow((levels > 1).then(|| ControlFlow::Break(levels - 1)));
+            true
+        }
+        Some(ControlFlow::Continue(levels)) if levels > 1 => {
+            shell.set_control_flow(Some(ControlFlow::Continue(levels - 1)));
+            true
+        }
+        Some(ControlFlow::Continue(_)) => {
+            shell.set_control_flow(None);
+            false
+        }
+        Some(ControlFlow::Return) => true,
+    }
+}
+
+/// Runs a `while` loop, or an `until` loop if `expected` is false.
+fn execute_while(shell: &mut dyn Shell, condition: &List, body: &List, expected: bool) -> i32 {
+    let mut code = 0;
+    loop {
+        let status = execute_list(shell, condition);
+        if loop_should_stop(shell) || (status == 0) != expected {
+            break;
+        }
+        code = execute_list(shell, body);
+        if loop_should_stop(shell) {
+            break;
+        }
+    }
+    code
+}
+
+fn execute_for(
+    shell: &mut dyn Shell,
+    name: &str,
+    words: Option<&[String]>,
+    body: &List,
+) -> anyhow::Result<i32> {
+    let expand = |shell: &mut dyn Shell| match words {
+        Some(words) => expand_words(shell, words),
+        None => Ok(shell.positional_parameters().to_vec()),
+    };
+
+    with_expansions(shell, expand, |shell, words| {
+        let mut code = 0;
+        for word in words {
+            shell.set_var(name, word)?;
+            code = execute_list(shell, body);
+            if loop_should_stop(shell) {
+                break;
+            }
+        }
+        Ok(code)
+    })
+}
+
+fn execute_case(shell: &mut dyn Shell, word: &str, items: &[CaseItem]) -> anyhow::Result<i32> {
+    with_expansions(
+        shell,
+        |shell| expand_word(shell, word),
+        |shell, word| execute_case_items(shell, &word, items),
+    )
+}
+
+fn execute_case_items(
+    shell: &mut dyn Shell,
+    word: &str,
+    items: &[CaseItem],
+) -> anyhow::Result<i32> {
+    let mut code = 0;
+    // Set by `;&`, the next body runs without testing its patterns.
+    let mut fall_through = false;
+
+    for item in items {
+        if !fall_through && !case_item_matches(shell, item, word)? {
+            continue;
+        }
+        code = if item.body.items.is_empty() {
+            0
+        } else {
+            execute_list(shell, &item.body)
+        };
+        if interrupted(shell) {
+            break;
+        }
+        match item.terminator {
+            CaseTerminator::Break => break,
+            CaseTerminator::FallThrough => fall_through = true,
+            CaseTerminator::Continue => fall_through = false,
+        }
+    }
+
+    Ok(code)
+}
+
+fn case_item_matches(shell: &mut dyn Shell, item: &CaseItem, word: &str) -> anyhow::Result<bool> {
+    for pattern in &item.patterns {
+        if expand_pattern(shell, pattern)?.matches(word) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
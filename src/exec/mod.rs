@@ -0,0 +1,1189 @@
+use std::{
+    ffi::{CString, NulError},
+    fs::{File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    os::{
+        fd::{FromRawFd, IntoRawFd, RawFd},
+        unix::{ffi::OsStringExt, fs::PermissionsExt},
+    },
+    path::{Path, PathBuf},
+    process::exit,
+};
+
+use anyhow::anyhow;
+use nix::{
+    errno::Errno,
+    fcntl::{fcntl, FcntlArg},
+    sys::{
+        memfd::{memfd_create, MemFdCreateFlag},
+        signal::{signal, SigHandler, Signal},
+    },
+    unistd::{close, dup2, execve, fork, getpid, pipe, setpgid, ForkResult, Pid},
+};
+
+use crate::{
+    builtins::{get_builtin, is_special_builtin},
+    error::{report, UnwrapPrintError},
+    expansion::{expand_heredoc, expand_word, expand_words, has_command_substitution},
+    parser::ast::{
+        AndOr, AndOrOperator, Assignment, Command, CompoundCommand, FunctionDefinition, List,
+        Pipeline, Redirectee, Redirection, RedirectionPermission, RedirectionType, SimpleCommand,
+    },
+    proc::{
+        job::{Job, Pgid},
+        terminal, ExternalProcesss, InternalProcess, Process, ProcessId, Status,
+    },
+    shell::{ControlFlow, Shell},
+};
+
+use self::compound::execute_compound;
+
+mod compound;
+
+/// The redirections of a command, which are applied one after the other
+/// so that `2>&1 >file` and `>file 2>&1` keep their different meanings.
+struct RedirectionHolder<'a> {
+    redirections: &'a [Redirection],
+    // Whether `>` refuses to overwrite existing files, see `set -o noclobber`.
+    noclobber: bool,
+    // The original file descriptors, with `None` for the ones that were not
+    // open, kept when the redirections have to be undone.
+    saved: Option<Vec<(RawFd, Option<RawFd>)>>,
+}
+
+impl<'a> RedirectionHolder<'a> {
+    const fn new(redirections: &'a [Redirection], noclobber: bool) -> Self {
+        Self {
+            redirections,
+            noclobber,
+            saved: None,
+        }
+    }
+
+    /// Redirections that can be undone with `restore`, for commands run in
+    /// the shell itself.
+    const fn restorable(redirections: &'a [Redirection], noclobber: bool) -> Self {
+        Self {
+            redirections,
+            noclobber,
+            saved: Some(Vec::new()),
+        }
+    }
+
+    fn open(&self, redirection: &Redirection, path: &str) -> anyhow::Result<RawFd> {
+        let mut options = OpenOptions::new();
+        match (redirection.type_, redirection.permissions) {
+            (RedirectionType::Input, _) => options.read(true),
+            (RedirectionType::ReadWrite, _) => options.read(true).write(true).create(true),
+            (RedirectionType::Output, RedirectionPermission::Append) => {
+                options.append(true).create(true)
+            }
+            (RedirectionType::Output, RedirectionPermission::Standard) if self.noclobber => {
+                // Only regular files are protected, so that `>/dev/null`
+                // keeps working.
+                match std::fs::metadata(path) {
+                    Ok(metadata) if metadata.is_file() => {
+                        return Err(anyhow!("{path}: cannot overwrite existing file"));
+                    }
+                    Ok(_) => options.write(true),
+                    Err(_) => options.write(true).create_new(true),
+                }
+            }
+            (RedirectionType::Output, _) => options.write(true).truncate(true).create(true),
+        };
+        let file = options.open(path).map_err(|e| {
+            let errno = Errno::from_i32(e.raw_os_error().unwrap_or(0));
+            anyhow!("{path}: {}", errno.desc())
+        })?;
+        Ok(file.into_raw_fd())
+    }
+
+    fn save(&mut self, fd: RawFd) {
+        if let Some(saved) = &mut self.saved {
+            if saved.iter().all(|(saved_fd, _)| *saved_fd != fd) {
+                let copy = fcntl(fd, FcntlArg::F_DUPFD_CLOEXEC(10)).ok();
+                saved.push((fd, copy));
+            }
+        }
+    }
+
+    fn apply(&mut self) -> anyhow::Result<()> {
+        for redirection in self.redirections {
+            let fd = redirection.fd;
+            match &redirection.redirectee {
+                Redirectee::FileName(path) => {
+                    let file = self.open(redirection, path)?;
+                    if file != fd {
+                        self.save(fd);
+                        let result = dup2(file, fd);
+                        close(file)?;
+                        result.map_err(|e| anyhow!("{fd}: {}", e.desc()))?;
+                    }
+                }
+                Redirectee::FileDescriptor(source) => {
+                    if *source != fd {
+                        fcntl(*source, FcntlArg::F_GETFD)
+                            .map_err(|e| anyhow!("{source}: {}", e.desc()))?;
+                        self.save(fd);
+                        dup2(*source, fd).map_err(|e| anyhow!("{fd}: {}", e.desc()))?;
+                    }
+                }
+                Redirectee::Close => {
+                    self.save(fd);
+                    let _ = close(fd);
+                }
+                Redirectee::HereDoc { body, .. } => self.feed(fd, body)?,
+                Redirectee::HereString(word) => self.feed(fd, &format!("{word}\n"))?,
+                // `expand_redirections` resolves it into one of the above.
+                Redirectee::Duplicate { word, .. } => {
+                    return Err(anyhow!("{word}: redirection not expanded"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes `fd` read the content of a here-document, through an anonymous
+    /// file so that large bodies cannot fill a pipe.
+    fn feed(&mut self, fd: RawFd, content: &str) -> anyhow::Result<()> {
+        let mut file = File::from(memfd_create(c"rjsh-heredoc", MemFdCreateFlag::MFD_CLOEXEC)?);
+        file.write_all(content.as_bytes())?;
+        file.seek(SeekFrom::Start(0))?;
+        let file = file.into_raw_fd();
+        self.save(fd);
+        let result = dup2(file, fd);
+        close(file)?;
+        result.map_err(|e| anyhow!("{fd}: {}", e.desc()))?;
+        Ok(())
+    }
+
+    /// Puts back the file descriptors replaced by `apply`.
+    fn restore(self) -> anyhow::Result<()> {
+        for (fd, copy) in self.saved.into_iter().flatten().rev() {
+            match copy {
+                Some(copy) => {
+                    dup2(copy, fd)?;
+                    close(copy)?;
+                }
+                None => {
+                    let _ = close(fd);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The pipe ends a pipeline stage has to wire to its standard input and output.
+#[derive(Default, Clone, Copy)]
+struct PipelineFds {
+    stdin: Option<RawFd>,
+    stdout: Option<RawFd>,
+    // Read end of the pipe connecting this stage to the next one, which the
+    // stage itself has to close.
+    next_stdin: Option<RawFd>,
+}
+
+impl PipelineFds {
+    fn dup_pipes(self) -> anyhow::Result<()> {
+        if let Some(fd) = self.next_stdin {
+            close(fd)?;
+        }
+        if let Some(fd) = self.stdin {
+            dup2(fd, 0)?;
+            close(fd)?;
+        }
+        if let Some(fd) = self.stdout {
+            dup2(fd, 1)?;
+            close(fd)?;
+        }
+        Ok(())
+    }
+
+    fn close_in_parent(self) -> anyhow::Result<()> {
+        if let Some(fd) = self.stdin {
+            close(fd)?;
+        }
+        if let Some(fd) = self.stdout {
+            close(fd)?;
+        }
+        Ok(())
+    }
+}
+
+/// Forks the shell, returning the pid of the child in the parent and `None`
+/// in the child.
+///
+/// Outside of subshells the child is moved to the process group `pgid`, or
+/// to a new one if it is `Pgid(0)`. With no `pgid` the child stays in the
+/// process group of the shell. The process group of a `foreground` child is
+/// given the terminal under job control.
+fn fork_child(
+    shell: &dyn Shell,
+    pgid: Option<Pgid>,
+    foreground: bool,
+) -> anyhow::Result<Option<ProcessId>> {
+    let pgid = pgid.filter(|_| !shell.is_subshell());
+    let foreground = foreground && shell.job_control();
+
+    match unsafe { fork()? } {
+        ForkResult::Parent { child } => {
+            if let Some(pgid) = pgid {
+                // Both the parent and the child set the process group to avoid
+                // racing against each other.
+                let pgid = if pgid.0 == 0 {
+                    child
+                } else {
+                    Pid::from_raw(pgid.0)
+                };
+                let _ = setpgid(child, pgid);
+            }
+            Ok(Some(ProcessId(child.as_raw())))
+        }
+        ForkResult::Child => {
+            if let Some(pgid) = pgid {
+                let pgid = if pgid.0 == 0 {
+                    getpid()
+                } else {
+                    Pid::from_raw(pgid.0)
+                };
+                if let Err(e) = setpgid(getpid(), pgid) {
+                    report(e);
+                    exit(1);
+                }
+                // The shell does it too, whichever comes first has to make
+                // sure the command does not start without the terminal.
+                if foreground {
+                    let _ = terminal::give_to(Pgid(pgid.as_raw()));
+                }
+            }
+
+            // The shell ignores SIGPIPE and the signals of job control,
+            // or catches SIGINT, children must not inherit that.
+            let reset = unsafe { signal(Signal::SIGPIPE, SigHandler::SigDfl) }
+                .and_then(|_| terminal::reset_signals());
+            if let Err(e) = reset {
+                report(e);
+                exit(1);
+            }
+
+            Ok(None)
+        }
+    }
+}
+
+/// Runs `f` in a forked copy of the shell and exits with the code it returns.
+fn fork_subshell<F>(shell: &mut dyn Shell, pgid: Option<Pgid>, f: F) -> anyhow::Result<ProcessId>
+where
+    F: FnOnce(&mut dyn Shell) -> i32,
+{
+    if let Some(child) = fork_child(shell, pgid, false)? {
+        return Ok(child);
+    }
+
+    shell.enter_subshell();
+    let exit_code = f(shell);
+    let _ = std::io::stdout().flush();
+    exit(exit_code);
+}
+
+fn prepare_child(redirections: &[Redirection], fds: PipelineFds, noclobber: bool) {
+    if let Err(e) = fds.dup_pipes() {
+        report(e);
+        exit(1);
+    }
+
+    if let Err(e) = RedirectionHolder::new(redirections, noclobber).apply() {
+        report(e);
+        exit(1);
+    }
+}
+
+fn fork_execute(
+    shell: &mut dyn Shell,
+    ast: SimpleCommand,
+    pgid: Pgid,
+    foreground: bool,
+    fds: PipelineFds,
+) -> anyhow::Result<ProcessId> {
+    if let Some(child) = fork_child(shell, Some(pgid), foreground)? {
+        return Ok(child);
+    }
+
+    prepare_child(&ast.redirections, fds, shell.options().noclobber);
+
+    if ast.name.is_empty() {
+        exit(assign_only(shell, &ast.assignments));
+    }
+    // The assignments only have to last as long as the child.
+    if let Err(e) = assign_variables(shell, &ast.assignments, true) {
+        report(e);
+        exit(1);
+    }
+
+    if let Some(builtin) = get_builtin(&ast) {
+        let exit_code = builtin.call(shell, &ast.args).unwrap_error_with_print();
+        let _ = std::io::stdout().flush();
+        exit(exit_code);
+    }
+
+    if let Some(function) = shell.function(&ast.name) {
+        shell.enter_subshell();
+        let exit_code = call_function(shell, &function, ast.args);
+        let _ = std::io::stdout().flush();
+        exit(exit_code);
+    }
+
+    let path = shell.get_var("PATH").unwrap_or_default();
+    let Some(executable) = find_executable(&ast.name, &path) else {
+        report(format_args!("{}: command not found", ast.name));
+        exit(127);
+    };
+
+    // Don't forget to add the command name to the args
+    let mut args = vec![ast.name.clone()];
+    args.extend(ast.args);
+
+    let to_c_strings = |strings: Vec<String>| -> Result<Vec<CString>, NulError> {
+        strings.into_iter().map(CString::new).collect()
+    };
+    let c_strings = to_c_strings(args).and_then(|c_args| {
+        let c_env = to_c_strings(shell.variables().environment())?;
+        let c_path = CString::new(executable.into_os_string().into_vec())?;
+        Ok((c_path, c_args, c_env))
+    });
+    let (c_path, c_args, c_env) = match c_strings {
+        Ok(c_strings) => c_strings,
+        Err(e) => {
+            report(format_args!("{}: {e}", ast.name));
+            exit(1);
+        }
+    };
+
+    let Err(e) = execve(c_path.as_ref(), c_args.as_ref(), c_env.as_ref());
+
+    report(format_args!("{}: {}", ast.name, e.desc()));
+
+    exit(if e == Errno::ENOENT { 127 } else { 126 });
+}
+
+/// Runs `f` in a forked copy of the shell, as a stage of a pipeline or in
+/// the background, for commands that are not executables.
+fn fork_internal<F>(
+    shell: &mut dyn Shell,
+    redirections: &[Redirection],
+    pgid: Pgid,
+    foreground: bool,
+    fds: PipelineFds,
+    f: F,
+) -> anyhow::Result<ProcessId>
+where
+    F: FnOnce(&mut dyn Shell) -> i32,
+{
+    if let Some(child) = fork_child(shell, Some(pgid), foreground)? {
+        return Ok(child);
+    }
+
+    prepare_child(redirections, fds, shell.options().noclobber);
+    shell.enter_subshell();
+    let exit_code = f(shell);
+    let _ = std::io::stdout().flush();
+    exit(exit_code);
+}
+
+/// Calls a function with `args` as its positional parameters and returns
+/// its exit code.
+fn call_function(shell: &mut dyn Shell, function: &FunctionDefinition, args: Vec<String>) -> i32 {
+    shell.push_frame(args);
+    // Loops of the caller cannot be left from inside the function.
+    let loop_depth = shell.loop_depth();
+    shell.set_loop_depth(0);
+
+    let exit_code = expand_redirections(shell, &function.redirections)
+        .and_then(|redirections| {
+            let name = function.body.to_string();
+            let stage = Stage::Compound(&function.body, redirections);
+            let job = commands_to_job(shell, vec![stage], vec![name.clone()], name, false)?;
+            wait_job(shell, job)
+        })
+        .unwrap_error_with_print();
+
+    if shell.control_flow() == Some(ControlFlow::Return) {
+        shell.set_control_flow(None);
+    }
+    shell.set_loop_depth(loop_depth);
+    shell.pop_frame();
+    exit_code
+}
+
+fn is_executable(path: &Path) -> bool {
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// Searches the directories of `path`, a colon separated list as in `$PATH`,
+/// for a file accepted by `accept`.
+pub fn search_path(name: &str, path: &str, accept: impl Fn(&Path) -> bool) -> Option<PathBuf> {
+    path.split(':')
+        .map(|dir| {
+            // An empty entry stands for the current directory.
+            let dir = if dir.is_empty() { "." } else { dir };
+            Path::new(dir).join(name)
+        })
+        .find(|candidate| accept(candidate))
+}
+
+/// Finds the executable run by a command, names containing a slash are
+/// paths and are not looked up in `path`.
+fn find_executable(name: &str, path: &str) -> Option<PathBuf> {
+    if name.contains('/') {
+        return Some(PathBuf::from(name));
+    }
+    search_path(name, path, is_executable)
+}
+
+/// Expands the words of a command, the result is ready to be executed.
+///
+/// The name of the command is empty if all its words expanded to nothing.
+fn expand_command(shell: &mut dyn Shell, command: &SimpleCommand) -> anyhow::Result<SimpleCommand> {
+    let mut words = vec![command.name.clone()];
+    words.extend(command.args.iter().cloned());
+    let mut fields = expand_words(shell, &words)?.into_iter();
+    let name = fields.next().unwrap_or_default();
+    let args = fields.collect();
+    let redirections = expand_redirections(shell, &command.redirections)?;
+
+    Ok(SimpleCommand::new(name, args, redirections).with_assignments(command.assignments.clone()))
+}
+
+/// Expands and performs assignments in order, exporting the variables if
+/// they are meant for the environment of a command.
+fn assign_variables(
+    shell: &mut dyn Shell,
+    assignments: &[Assignment],
+    export: bool,
+) -> anyhow::Result<()> {
+    for assignment in assignments {
+        let value = expand_word(shell, &assignment.value)?;
+        shell.set_var(&assignment.name, value)?;
+        if export {
+            shell.variables_mut().set_exported(&assignment.name, true);
+        }
+    }
+    Ok(())
+}
+
+/// Performs the assignments of a command without a name. Its exit code is
+/// the one of the last command substitution, or 0 if there is none.
+fn assign_only(shell: &mut dyn Shell, assignments: &[Assignment]) -> i32 {
+    let substitutes = assignments
+        .iter()
+        .any(|assignment| has_command_substitution(&assignment.value));
+    assign_variables(shell, assignments, false)
+        .map(|()| {
+            if substitutes {
+                shell.last_exit_code()
+            } else {
+                0
+            }
+        })
+        .unwrap_error_with_print()
+}
+
+/// Runs `f` with the assignments prefixing a builtin or a function exported,
+/// and puts back the variables they replaced afterwards.
+fn with_assignments<F>(shell: &mut dyn Shell, assignments: &[Assignment], f: F) -> i32
+where
+    F: FnOnce(&mut dyn Shell) -> i32,
+{
+    let saved: Vec<_> = assignments
+        .iter()
+        .map(|assignment| {
+            let previous = shell.variables().get(&assignment.name).cloned();
+            (assignment.name.clone(), previous)
+        })
+        .collect();
+
+    let exit_code = match assign_variables(shell, assignments, true) {
+        Ok(()) => f(shell),
+        Err(e) => {
+            report(e);
+            1
+        }
+    };
+
+    for (name, previous) in saved.into_iter().rev() {
+        shell.variables_mut().restore(name, previous);
+    }
+    exit_code
+}
+
+/// Expands the targets of redirections and the bodies of here-documents.
+fn expand_redirections(
+    shell: &mut dyn Shell,
+    redirections: &[Redirection],
+) -> anyhow::Result<Vec<Redirection>> {
+    let mut expanded = Vec::new();
+    for redirection in redirections {
+        let redirectee = match &redirection.redirectee {
+            Redirectee::FileName(path) => Redirectee::FileName(expand_word(shell, path)?),
+            Redirectee::Duplicate { word, fd_given } => {
+                let word = expand_word(shell, word)?;
+                if word == "-" {
+                    Redirectee::Close
+                } else if !word.is_empty() && word.bytes().all(|b| b.is_ascii_digit()) {
+                    Redirectee::FileDescriptor(
+                        word.parse()
+                            .map_err(|_| anyhow!("{word}: bad file descriptor"))?,
+                    )
+                } else if redirection.type_ == RedirectionType::Output && !fd_given {
+                    // `>&file` is `>file 2>&1`.
+                    let file = Redirectee::FileName(word);
+                    expanded.extend([
+                        Redirection::new(1, file, RedirectionType::Output, redirection.permissions),
+                        Redirection::new(
+                            2,
+                            Redirectee::FileDescriptor(1),
+                            RedirectionType::Output,
+                            RedirectionPermission::Standard,
+                        ),
+                    ]);
+                    continue;
+                } else {
+                    return Err(anyhow!("{word}: ambiguous redirect"));
+                }
+            }
+            Redirectee::HereDoc { body, expand: true } => Redirectee::HereDoc {
+                body: expand_heredoc(shell, body)?,
+                expand: false,
+            },
+            Redirectee::HereString(word) => Redirectee::HereDoc {
+                body: format!("{}\n", expand_word(shell, word)?),
+                expand: false,
+            },
+            redirectee => redirectee.clone(),
+        };
+        expanded.push(Redirection::new(
+            redirection.fd,
+            redirectee,
+            redirection.type_,
+            redirection.permissions,
+        ));
+    }
+    Ok(expanded)
+}
+
+/// Runs `f` in the shell itself with the redirections applied, undoing them
+/// afterwards.
+fn call_in_parent<F>(shell: &mut dyn Shell, redirections: &[Redirection], f: F) -> i32
+where
+    F: FnOnce(&mut dyn Shell) -> i32,
+{
+    let _ = std::io::stdout().flush();
+    let mut redirections = RedirectionHolder::restorable(redirections, shell.options().noclobber);
+    let exit_code = match redirections.apply() {
+        Ok(()) => f(shell),
+        Err(e) => {
+            report(e);
+            1
+        }
+    };
+    let _ = std::io::stdout().flush();
+    if let Err(e) = redirections.restore() {
+        report(e);
+    }
+    exit_code
+}
+
+/// A command of a pipeline, expanded before it is started. The words of
+/// compound commands are only expanded as they run.
+enum Stage<'a> {
+    Simple(SimpleCommand),
+    Compound(&'a CompoundCommand, Vec<Redirection>),
+    Function(&'a FunctionDefinition),
+}
+
+fn pipeline_to_job(shell: &mut dyn Shell, ast: &Pipeline, background: bool) -> anyhow::Result<Job> {
+    let name = ast.to_string();
+    let process_names: Vec<String> = ast.commands.iter().map(ToString::to_string).collect();
+    let commands = ast
+        .commands
+        .iter()
+        .map(|command| match command {
+            Command::Simple(command) => Ok(Stage::Simple(expand_command(shell, command)?)),
+            Command::Compound(command, redirections) => Ok(Stage::Compound(
+                command,
+                expand_redirections(shell, redirections)?,
+            )),
+            Command::Function(function) => Ok(Stage::Function(function)),
+        })
+        .collect::<anyhow::Result<Vec<_>>>();
+    let commands = match commands {
+        Ok(commands) => commands,
+        Err(e) => {
+            close_process_substitutions(shell.take_process_substitutions())?;
+            return Err(e);
+        }
+    };
+
+    // The process substitutions of the commands only have to stay open in
+    // the shell until the commands are started.
+    let mut substitutions = shell.take_process_substitutions();
+    let result = commands_to_job(shell, commands, process_names, name, background);
+    // Assignments run by the shell itself may have expanded some as well.
+    substitutions.extend(shell.take_process_substitutions());
+    let mut auxiliary: Vec<Box<dyn Process>> = Vec::new();
+    for (process, fd) in substitutions {
+        close(fd)?;
+        auxiliary.push(Box::new(process));
+    }
+
+    match result {
+        Ok(mut job) => {
+            job.add_auxiliary(auxiliary);
+            Ok(job)
+        }
+        Err(e) => {
+            for mut process in auxiliary {
+                process.wait(true)?;
+            }
+            Err(e)
+        }
+    }
+}
+
+fn commands_to_job(
+    shell: &mut dyn Shell,
+    commands: Vec<Stage>,
+    process_names: Vec<String>,
+    name: String,
+    background: bool,
+) -> anyhow::Result<Job> {
+    // A lone builtin in the foreground has to run in the shell itself,
+    // otherwise commands like `cd` or `exit` would be useless. So do lone
+    // functions and compound commands, for the variables they set to stay
+    // set.
+    if !background && commands.len() == 1 {
+        let exit_code = match &commands[0] {
+            Stage::Simple(command) => {
+                if let Some(builtin) = get_builtin(command) {
+                    Some(call_in_parent(shell, &command.redirections, |shell| {
+                        let call = |shell: &mut dyn Shell| {
+                            builtin.call(shell, &command.args).unwrap_error_with_print()
+                        };
+                        // The assignments of special builtins stay in effect.
+                        if !is_special_builtin(&command.name) {
+                            return with_assignments(shell, &command.assignments, call);
+                        }
+                        match assign_variables(shell, &command.assignments, false) {
+                            Ok(()) => call(shell),
+                            Err(e) => {
+                                report(e);
+                                1
+                            }
+                        }
+                    }))
+                } else if let Some(function) = shell.function(&command.name) {
+                    Some(call_in_parent(shell, &command.redirections, |shell| {
+                        with_assignments(shell, &command.assignments, |shell| {
+                            call_function(shell, &function, command.args.clone())
+                        })
+                    }))
+                } else if command.name.is_empty() {
+                    Some(call_in_parent(shell, &command.redirections, |shell| {
+                        assign_only(shell, &command.assignments)
+                    }))
+                } else {
+                    None
+                }
+            }
+            Stage::Function(function) => {
+                shell.define_function((*function).clone());
+                Some(0)
+            }
+            Stage::Compound(CompoundCommand::Subshell(_), _) => None,
+            Stage::Compound(command, redirections) => {
+                Some(call_in_parent(shell, redirections, |shell| {
+                    execute_compound(shell, command)
+                }))
+            }
+        };
+        if let Some(exit_code) = exit_code {
+            // Better handle this. The job is not properly printed etc...
+            let process = InternalProcess::new(name.clone(), exit_code);
+            return Ok(Job::new(
+                Pgid(0),
+                vec![Box::new(process)],
+                Status::Done,
+                background,
+                name,
+            ));
+        }
+    }
+
+    let mut pgid = Pgid(0);
+    let mut processes: Vec<Box<dyn Process>> = Vec::new();
+    let mut stdin = None;
+    let stages = commands.len();
+
+    for (i, (command, process_name)) in commands.into_iter().zip(process_names).enumerate() {
+        let (next_stdin, stdout) = if i + 1 < stages {
+            let (read, write) = pipe()?;
+            (Some(read), Some(write))
+        } else {
+            (None, None)
+        };
+        let fds = PipelineFds {
+            stdin,
+            stdout,
+            next_stdin,
+        };
+
+        let result = match command {
+            Stage::Simple(command) => fork_execute(shell, command, pgid, !background, fds),
+            Stage::Compound(command, redirections) => {
+                fork_internal(shell, &redirections, pgid, !background, fds, |shell| {
+                    execute_compound(shell, command)
+                })
+            }
+            Stage::Function(_) => fork_internal(shell, &[], pgid, !background, fds, |_| 0),
+        };
+        fds.close_in_parent()?;
+        let child_pid = match result {
+            Ok(pid) => pid,
+            Err(e) => {
+                if let Some(fd) = next_stdin {
+                    close(fd)?;
+                }
+                return Err(e);
+            }
+        };
+
+        if pgid.0 == 0 {
+            pgid = Pgid(child_pid.0);
+        }
+        processes.push(Box::new(ExternalProcesss::new(child_pid, process_name)));
+        stdin = next_stdin;
+    }
+
+    Ok(Job::new(pgid, processes, Status::Running, background, name))
+}
+
+/// Waits for a job started in the foreground, or registers it in the job
+/// table if it has to keep running, and returns its exit code.
+///
+/// Background jobs are registered even if they are done already, so that
+/// `wait` can report their status, and their exit code is 0.
+fn wait_job(shell: &mut dyn Shell, mut job: Job) -> anyhow::Result<i32> {
+    if job.background {
+        if let Some(process) = job.processes.last() {
+            shell.set_last_background_pid(process.pid().0);
+        }
+        job.notify = shell.job_control();
+        shell.add_job(job);
+        return Ok(0);
+    }
+
+    job.wait_in_foreground(shell.job_control())?;
+    // The job got the SIGINT of the terminal instead of the shell.
+    let killed = job.exit_status().and_then(|status| status.killed());
+    if shell.job_control() && killed == Some(Signal::SIGINT as i32) {
+        terminal::interrupt();
+    }
+    match job.last_status {
+        Status::Done | Status::Killed => Ok(job
+            .exit_status()
+            .expect("rjsh: wow, that should not happen")
+            .to_exit_code()),
+        Status::Running | Status::Stopped => {
+            let code = job.exit_status().map_or(0, |status| status.to_exit_code());
+            job.notify = shell.job_control();
+            let id = shell.add_job(job);
+            // A job stopped in the foreground is only known by its id now.
+            if let Some(job) = shell.job_table().get_job(id) {
+                println!("{job}");
+            }
+            Ok(code)
+        }
+    }
+}
+
+pub fn execute_pipeline(
+    shell: &mut dyn Shell,
+    pipeline: &Pipeline,
+    background: bool,
+) -> anyhow::Result<i32> {
+    let job = pipeline_to_job(shell, pipeline, background)?;
+    wait_job(shell, job)
+}
+
+/// Executes an and-or list in the foreground, skipping the pipelines whose
+/// operator is short-circuited by the previous exit code.
+pub fn execute_and_or(shell: &mut dyn Shell, and_or: &AndOr) -> i32 {
+    let mut code = execute_pipeline(shell, &and_or.first, false).unwrap_error_with_print();
+    shell.set_last_exit_code(code);
+
+    for (operator, pipeline) in &and_or.rest {
+        if shell.should_exit() || shell.control_flow().is_some() || terminal::interrupted() {
+            break;
+        }
+        let run = match operator {
+            AndOrOperator::And => code == 0,
+            AndOrOperator::Or => code != 0,
+        };
+        if run {
+            code = execute_pipeline(shell, pipeline, false).unwrap_error_with_print();
+            shell.set_last_exit_code(code);
+        }
+    }
+
+    code
+}
+
+/// Starts an and-or list in the background. A lone pipeline becomes a job of
+/// its own, while longer lists are run by a subshell so that the whole list
+/// is a single job.
+fn execute_background_and_or(shell: &mut dyn Shell, and_or: &AndOr) -> anyhow::Result<i32> {
+    if and_or.rest.is_empty() {
+        return execute_pipeline(shell, &and_or.first, true);
+    }
+
+    let name = and_or.to_string();
+    let child = fork_subshell(shell, Some(Pgid(0)), |shell| execute_and_or(shell, and_or))?;
+    let process = ExternalProcesss::new(child, name.clone());
+    let job = Job::new(
+        Pgid(child.0),
+        vec![Box::new(process)],
+        Status::Running,
+        true,
+        name,
+    );
+    wait_job(shell, job)
+}
+
+pub fn execute_list(shell: &mut dyn Shell, list: &List) -> i32 {
+    let mut code = shell.last_exit_code();
+
+    for item in &list.items {
+        if shell.should_exit() || shell.control_flow().is_some() || terminal::interrupted() {
+            break;
+        }
+        code = if item.background {
+            let code = execute_background_and_or(shell, &item.and_or).unwrap_error_with_print();
+            shell.set_last_exit_code(code);
+            code
+        } else {
+            execute_and_or(shell, &item.and_or)
+        };
+    }
+
+    code
+}
+
+/// Runs a list in a subshell and returns what it wrote on its standard output,
+/// without the trailing newlines, along with its exit code.
+pub fn command_substitution(shell: &mut dyn Shell, list: &List) -> anyhow::Result<(String, i32)> {
+    let (read, write) = pipe()?;
+
+    let child = fork_subshell(shell, None, |shell| {
+        let redirected = close(read)
+            .and_then(|()| dup2(write, 1))
+            .and_then(|_| close(write));
+        if let Err(e) = redirected {
+            report(e);
+            return 1;
+        }
+        execute_list(shell, list)
+    });
+    close(write)?;
+    let child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            close(read)?;
+            return Err(e);
+        }
+    };
+
+    let mut output = Vec::new();
+    let read_result = unsafe { File::from_raw_fd(read) }.read_to_end(&mut output);
+
+    let mut process = ExternalProcesss::new(child, String::new());
+    process.wait(true)?;
+    read_result?;
+
+    let code = process
+        .exit_status()
+        .map_or(0, |status| status.to_exit_code());
+    let mut output = String::from_utf8_lossy(&output).into_owned();
+    output.truncate(output.trim_end_matches('\n').len());
+
+    Ok((output, code))
+}
+
+/// Closes the end of process substitutions kept by the shell, and waits for
+/// their processes.
+fn close_process_substitutions(
+    substitutions: Vec<(ExternalProcesss, RawFd)>,
+) -> anyhow::Result<()> {
+    for (mut process, fd) in substitutions {
+        close(fd)?;
+        process.wait(true)?;
+    }
+    Ok(())
+}
+
+/// Expands the words of a compound command with `expand`, then runs the
+/// command with `f`. The process substitutions of the words belong to the
+/// command and stay open until it is done, whether the expansion succeeded
+/// or not.
+fn with_expansions<T, R>(
+    shell: &mut dyn Shell,
+    expand: impl FnOnce(&mut dyn Shell) -> anyhow::Result<T>,
+    f: impl FnOnce(&mut dyn Shell, T) -> anyhow::Result<R>,
+) -> anyhow::Result<R> {
+    let expanded = expand(shell);
+    let substitutions = shell.take_process_substitutions();
+    let result = expanded.and_then(|expanded| f(shell, expanded));
+    close_process_substitutions(substitutions)?;
+    result
+}
+
+/// Starts a process substitution and returns the `/dev/fd/N` path the
+/// command reads the output of `list` from, or writes its input to for
+/// `>(list)`.
+pub fn process_substitution(
+    shell: &mut dyn Shell,
+    list: &List,
+    output: bool,
+) -> anyhow::Result<String> {
+    let (read, write) = pipe()?;
+    let (ours, theirs, target) = if output {
+        (write, read, 0)
+    } else {
+        (read, write, 1)
+    };
+
+    let child = fork_subshell(shell, None, |shell| {
+        let redirected = close(ours)
+            .and_then(|()| dup2(theirs, target))
+            .and_then(|_| close(theirs));
+        if let Err(e) = redirected {
+            report(e);
+            return 1;
+        }
+        execute_list(shell, list)
+    });
+    close(theirs)?;
+    let child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            close(ours)?;
+            return Err(e);
+        }
+    };
+
+    shell.add_process_substitution(ExternalProcesss::new(child, String::new()), ours);
+    Ok(format!("/dev/fd/{ours}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{shell::DefaultShell, test_util::run};
+
+    use super::*;
+
+    fn is_open(fd: RawFd) -> bool {
+        fcntl(fd, FcntlArg::F_GETFD).is_ok()
+    }
+
+    #[test]
+    fn test_redirections_restore() {
+        let path = std::env::temp_dir().join(format!("rjsh-redirect-{}", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let redirections = [
+            Redirection::new(
+                60,
+                Redirectee::FileName(path.clone()),
+                RedirectionType::Output,
+                RedirectionPermission::Standard,
+            ),
+            Redirection::new(
+                61,
+                Redirectee::FileDescriptor(60),
+                RedirectionType::Output,
+                RedirectionPermission::Standard,
+            ),
+        ];
+
+        let mut holder = RedirectionHolder::restorable(&redirections, false);
+        holder.apply().unwrap();
+        assert!(is_open(60) && is_open(61));
+        holder.restore().unwrap();
+        assert!(!is_open(60) && !is_open(61));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_redirections_noclobber() {
+        let path = std::env::temp_dir().join(format!("rjsh-noclobber-{}", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let redirect = |permissions| {
+            let redirections = [Redirection::new(
+                64,
+                Redirectee::FileName(path.clone()),
+                RedirectionType::Output,
+                permissions,
+            )];
+            let mut holder = RedirectionHolder::restorable(&redirections, true);
+            let result = holder.apply();
+            holder.restore().unwrap();
+            result
+        };
+
+        assert!(redirect(RedirectionPermission::Standard).is_ok());
+        let error = redirect(RedirectionPermission::Standard).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("{path}: cannot overwrite existing file")
+        );
+        assert!(redirect(RedirectionPermission::Truncate).is_ok());
+        assert!(redirect(RedirectionPermission::Append).is_ok());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_redirection_errors() {
+        let redirections = [Redirection::new(
+            62,
+            Redirectee::FileName("/rjsh/missing".into()),
+            RedirectionType::Input,
+            RedirectionPermission::Standard,
+        )];
+        let error = RedirectionHolder::restorable(&redirections, false)
+            .apply()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "/rjsh/missing: No such file or directory"
+        );
+
+        let redirections = [Redirection::new(
+            62,
+            Redirectee::FileDescriptor(63),
+            RedirectionType::Output,
+            RedirectionPermission::Standard,
+        )];
+        assert!(RedirectionHolder::restorable(&redirections, false)
+            .apply()
+            .is_err());
+        assert!(!is_open(62));
+    }
+
+    #[test]
+    fn test_background_job() {
+        let mut shell = DefaultShell::default();
+        assert_eq!(run(&mut shell, "false &"), 0);
+        assert!(shell.last_background_pid().is_some());
+
+        // The job is kept after it is done, for `wait` to report its status.
+        let id = shell.job_table().current().unwrap();
+        let job = shell.job_table_mut().get_job_mut(id).unwrap();
+        while !job.last_status.is_finished() {
+            job.update(true).unwrap();
+        }
+        assert_eq!(job.exit_status().unwrap().to_exit_code(), 1);
+    }
+
+    #[test]
+    fn test_prefix_assignments() {
+        let mut shell = DefaultShell::default();
+
+        // Special builtins keep the assignments, other commands do not.
+        assert_eq!(run(&mut shell, "a=1 :; b=2 eval 'c=$b'; d=3 jobs"), 0);
+        assert_eq!(shell.get_var("a").as_deref(), Some("1"));
+        assert_eq!(shell.get_var("b").as_deref(), Some("2"));
+        assert_eq!(shell.get_var("c").as_deref(), Some("2"));
+        assert_eq!(shell.get_var("d"), None);
+
+        run(&mut shell, "e=1; e=2 jobs; f() { g=$e; }; e=3 f");
+        assert_eq!(shell.get_var("e").as_deref(), Some("1"));
+        assert_eq!(shell.get_var("g").as_deref(), Some("3"));
+
+        run(&mut shell, "set -- x y z; shift 2");
+        assert_eq!(shell.positional_parameters(), ["z"]);
+    }
+
+    #[test]
+    fn test_expand_duplications() {
+        let mut shell = DefaultShell::default();
+        shell.set_var("fd", String::from("2")).unwrap();
+        shell.set_var("close", String::from("-")).unwrap();
+        let duplicate = |fd, word: &str, fd_given| {
+            let word = word.to_string();
+            let redirectee = Redirectee::Duplicate { word, fd_given };
+            Redirection::new(
+                fd,
+                redirectee,
+                RedirectionType::Output,
+                RedirectionPermission::Standard,
+            )
+        };
+        let output = |fd, redirectee| {
+            Redirection::new(
+                fd,
+                redirectee,
+                RedirectionType::Output,
+                RedirectionPermission::Standard,
+            )
+        };
+
+        let redirections = [
+            duplicate(1, "$fd", false),
+            duplicate(1, "\"2\"", true),
+            duplicate(3, "$close", true),
+        ];
+        assert_eq!(
+            expand_redirections(&mut shell, &redirections).unwrap(),
+            [
+                output(1, Redirectee::FileDescriptor(2)),
+                output(1, Redirectee::FileDescriptor(2)),
+                output(3, Redirectee::Close),
+            ]
+        );
+
+        // Only `>&file` without a number stands for `&>file`.
+        assert_eq!(
+            expand_redirections(&mut shell, &[duplicate(1, "f$fd", false)]).unwrap(),
+            [
+                output(1, Redirectee::FileName("f2".into())),
+                output(2, Redirectee::FileDescriptor(1)),
+            ]
+        );
+        let error = expand_redirections(&mut shell, &[duplicate(1, "f", true)]).unwrap_err();
+        assert_eq!(error.to_string(), "f: ambiguous redirect");
+    }
+
+    #[test]
+    fn test_process_substitution_owners() {
+        let mut shell = DefaultShell::default();
+
+        // Substitutions are not left to the next pipeline after an error.
+        assert_eq!(run(&mut shell, "echo <(echo a) $((1 / 0))"), 1);
+        assert!(shell.take_process_substitutions().is_empty());
+        run(&mut shell, "for f in <(echo a) $((1 / 0)); do :; done");
+        assert!(shell.take_process_substitutions().is_empty());
+
+        // The ones of a loop stay open while its body runs pipelines.
+        run(
+            &mut shell,
+            "for f in <(echo one); do true | true; v=$(cat $f); done",
+        );
+        assert_eq!(shell.get_var("v").as_deref(), Some("one"));
+        run(
+            &mut shell,
+            "case <(echo two) in /dev/fd/*) true | true; w=1;; esac",
+        );
+        assert_eq!(shell.get_var("w").as_deref(), Some("1"));
+        assert!(shell.take_process_substitutions().is_empty());
+    }
+}
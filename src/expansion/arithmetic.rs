@@ -0,0 +1,522 @@
+use anyhow::anyhow;
+
+use crate::shell::variables::Variables;
+
+/// How deep variables whose values are expressions can refer to each
+/// other, to stop on `a=a`.
+const MAX_RECURSION: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(i64),
+    Name(String),
+    Operator(&'static str),
+}
+
+/// The operators, longer ones first so that they are preferred.
+const OPERATORS: [&str; 37] = [
+    "<<=", ">>=", "**", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "*=", "/=",
+    "%=", "+=", "-=", "&=", "^=", "|=", "+", "-", "*", "/", "%", "<", ">", "&", "|", "^", "!", "~",
+    "?", ":", "=", ",",
+];
+
+#[derive(Debug)]
+enum Expr {
+    Number(i64),
+    Variable(String),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+    /// An assignment, with the operator of compound assignments like `+=`.
+    Assign(String, Option<&'static str>, Box<Expr>),
+    /// `++x` or `--x` when `prefix`, `x++` or `x--` otherwise.
+    Increment {
+        name: String,
+        delta: i64,
+        prefix: bool,
+    },
+}
+
+/// Parses an integer constant: decimal, octal with a leading `0`,
+/// hexadecimal with `0x` or in any base from 2 to 64 as `base#digits`.
+fn parse_number(text: &str) -> anyhow::Result<i64> {
+    let (base, digits) = if let Some((base, digits)) = text.split_once('#') {
+        let base = base
+            .parse::<u32>()
+            .ok()
+            .filter(|base| (2..=64).contains(base))
+            .ok_or_else(|| anyhow!("{text}: invalid arithmetic base"))?;
+        (base, digits)
+    } else if let Some(digits) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        (16, digits)
+    } else if text.len() > 1 && text.starts_with('0') {
+        (8, &text[1..])
+    } else {
+        (10, text)
+    };
+    if digits.is_empty() {
+        return Err(anyhow!("{text}: invalid integer constant"));
+    }
+
+    let mut value: i64 = 0;
+    for c in digits.chars() {
+        // Up to base 36 letters are case insensitive, above lowercase
+        // letters come first, then uppercase ones, `@` and `_`.
+        let digit = match c {
+            '0'..='9' => c as u32 - '0' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 10,
+            'A'..='Z' if base <= 36 => c as u32 - 'A' as u32 + 10,
+            'A'..='Z' => c as u32 - 'A' as u32 + 36,
+            '@' => 62,
+            '_' => 63,
+            _ => return Err(anyhow!("{text}: invalid number")),
+        };
+        if digit >= base {
+            return Err(anyhow!("{text}: value too great for base"));
+        }
+        value = value
+            .wrapping_mul(i64::from(base))
+            .wrapping_add(i64::from(digit));
+    }
+    Ok(value)
+}
+
+fn tokenize(expression: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = expression.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let end = if c.is_ascii_digit() {
+            // Constants in a base, like `64#@_`, include `#`, `@` and `_`.
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '#' | '@')))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Number(parse_number(&rest[..end])?));
+            end
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..end].to_string()));
+            end
+        } else if c == '(' || c == ')' {
+            tokens.push(Token::Operator(if c == '(' { "(" } else { ")" }));
+            1
+        } else {
+            let operator = OPERATORS
+                .iter()
+                .find(|operator| rest.starts_with(*operator))
+                .ok_or_else(|| {
+                    anyhow!("{expression}: syntax error: invalid arithmetic operator (error token is \"{rest}\")")
+                })?;
+            tokens.push(Token::Operator(operator));
+            operator.len()
+        };
+        rest = rest[end..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/// A precedence climbing parser over the tokens of an expression.
+struct Parser<'a> {
+    expression: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+/// The binary operators from the lowest precedence to the highest, `**` and
+/// the assignments and conditionals being handled apart.
+const BINARY_LEVELS: [&[&str]; 10] = [
+    &["||"],
+    &["&&"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["==", "!="],
+    &["<", ">", "<=", ">="],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_operator(&self) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Operator(operator)) => Some(operator),
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn error(&self, message: &str) -> anyhow::Error {
+        let token = match self.peek() {
+            Some(Token::Number(number)) => number.to_string(),
+            Some(Token::Name(name)) => name.clone(),
+            Some(Token::Operator(operator)) => (*operator).to_string(),
+            None => String::new(),
+        };
+        anyhow!(
+            "{}: syntax error: {message} (error token is \"{token}\")",
+            self.expression.trim()
+        )
+    }
+
+    fn expect(&mut self, operator: &str) -> anyhow::Result<()> {
+        if self.peek_operator() == Some(operator) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("`{operator}' expected")))
+        }
+    }
+
+    fn parse_comma(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.parse_assignment()?;
+        while self.peek_operator() == Some(",") {
+            self.position += 1;
+            let right = self.parse_assignment()?;
+            expr = Expr::Binary(",", Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    fn parse_assignment(&mut self) -> anyhow::Result<Expr> {
+        if let (Some(Token::Name(name)), Some(Token::Operator(operator))) =
+            (self.peek(), self.tokens.get(self.position + 1))
+        {
+            let compound = match *operator {
+                "=" => Some(None),
+                "*=" | "/=" | "%=" | "+=" | "-=" | "<<=" | ">>=" | "&=" | "^=" | "|=" => {
+                    Some(Some(&operator[..operator.len() - 1]))
+                }
+                _ => None,
+            };
+            if let Some(compound) = compound {
+                let name = name.clone();
+                self.position += 2;
+                let value = self.parse_assignment()?;
+                return Ok(Expr::Assign(name, compound, Box::new(value)));
+            }
+        }
+        self.parse_conditional()
+    }
+
+    fn parse_conditional(&mut self) -> anyhow::Result<Expr> {
+        let condition = self.parse_binary(0)?;
+        if self.peek_operator() != Some("?") {
+            return Ok(condition);
+        }
+        self.position += 1;
+        let then = self.parse_comma()?;
+        self.expect(":")?;
+        let otherwise = self.parse_assignment()?;
+        Ok(Expr::Conditional(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn parse_binary(&mut self, level: usize) -> anyhow::Result<Expr> {
+        let Some(operators) = BINARY_LEVELS.get(level) else {
+            return self.parse_power();
+        };
+        let mut expr = self.parse_binary(level + 1)?;
+        while let Some(operator) = self.peek_operator().filter(|o| operators.contains(o)) {
+            self.position += 1;
+            let right = self.parse_binary(level + 1)?;
+            expr = Expr::Binary(operator, Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> anyhow::Result<Expr> {
+        match self.peek_operator() {
+            Some(operator @ ("++" | "--")) => {
+                self.position += 1;
+                match self.next() {
+                    Some(Token::Name(name)) => Ok(Expr::Increment {
+                        name,
+                        delta: if operator == "++" { 1 } else { -1 },
+                        prefix: true,
+                    }),
+                    _ => {
+                        self.position -= 1;
+                        Err(self.error("operand expected"))
+                    }
+                }
+            }
+            Some(operator @ ("-" | "+" | "!" | "~")) => {
+                self.position += 1;
+                let operand = self.parse_unary()?;
+                Ok(Expr::Unary(operator, Box::new(operand)))
+            }
+            _ => self.parse_postfix(),
+        }
+    }
+
+    /// `**` is right associative and binds tighter than the other binary
+    /// operators, but not than the unary ones.
+    fn parse_power(&mut self) -> anyhow::Result<Expr> {
+        let base = self.parse_unary()?;
+        if self.peek_operator() != Some("**") {
+            return Ok(base);
+        }
+        self.position += 1;
+        let exponent = self.parse_power()?;
+        Ok(Expr::Binary("**", Box::new(base), Box::new(exponent)))
+    }
+
+    fn parse_postfix(&mut self) -> anyhow::Result<Expr> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Expr::Number(number)),
+            Some(Token::Name(name)) => match self.peek_operator() {
+                Some(operator @ ("++" | "--")) => {
+                    self.position += 1;
+                    Ok(Expr::Increment {
+                        name,
+                        delta: if operator == "++" { 1 } else { -1 },
+                        prefix: false,
+                    })
+                }
+                _ => Ok(Expr::Variable(name)),
+            },
+            Some(Token::Operator("(")) => {
+                let expr = self.parse_comma()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            _ => {
+                self.position -= 1;
+                Err(self.error("operand expected"))
+            }
+        }
+    }
+}
+
+struct Evaluator<'a> {
+    variables: &'a mut Variables,
+    depth: usize,
+}
+
+impl Evaluator<'_> {
+    /// The value of a variable, whose content is itself evaluated as an
+    /// expression. Unset and empty variables are 0.
+    fn variable(&mut self, name: &str) -> anyhow::Result<i64> {
+        let value = self.variables.value(name).unwrap_or_default().to_string();
+        if value.trim().is_empty() {
+            return Ok(0);
+        }
+        if let Ok(value) = parse_number(value.trim()) {
+            return Ok(value);
+        }
+        if self.depth >= MAX_RECURSION {
+            return Err(anyhow!("{name}: expression recursion level exceeded"));
+        }
+        self.depth += 1;
+        let result = self.evaluate_str(&value);
+        self.depth -= 1;
+        result
+    }
+
+    fn assign(&mut self, name: &str, value: i64) -> anyhow::Result<i64> {
+        self.variables.set(name, value.to_string())?;
+        Ok(value)
+    }
+
+    fn evaluate_str(&mut self, expression: &str) -> anyhow::Result<i64> {
+        let tokens = tokenize(expression)?;
+        if tokens.is_empty() {
+            return Ok(0);
+        }
+        let mut parser = Parser {
+            expression,
+            tokens,
+            position: 0,
+        };
+        let expr = parser.parse_comma()?;
+        if parser.peek().is_some() {
+            return Err(parser.error("invalid arithmetic operator"));
+        }
+        self.evaluate(&expr)
+    }
+
+    fn evaluate(&mut self, expr: &Expr) -> anyhow::Result<i64> {
+        match expr {
+            Expr::Number(number) => Ok(*number),
+            Expr::Variable(name) => self.variable(name),
+            Expr::Unary(operator, operand) => {
+                let value = self.evaluate(operand)?;
+                Ok(match *operator {
+                    "-" => value.wrapping_neg(),
+                    "!" => i64::from(value == 0),
+                    "~" => !value,
+                    _ => value,
+                })
+            }
+            Expr::Binary("&&", left, right) => Ok(i64::from(
+                self.evaluate(left)? != 0 && self.evaluate(right)? != 0,
+            )),
+            Expr::Binary("||", left, right) => Ok(i64::from(
+                self.evaluate(left)? != 0 || self.evaluate(right)? != 0,
+            )),
+            Expr::Binary(operator, left, right) => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                apply(operator, left, right)
+            }
+            Expr::Conditional(condition, then, otherwise) => {
+                if self.evaluate(condition)? != 0 {
+                    self.evaluate(then)
+                } else {
+                    self.evaluate(otherwise)
+                }
+            }
+            Expr::Assign(name, operator, value) => {
+                let value = self.evaluate(value)?;
+                let value = match operator {
+                    Some(operator) => apply(operator, self.variable(name)?, value)?,
+                    None => value,
+                };
+                self.assign(name, value)
+            }
+            Expr::Increment {
+                name,
+                delta,
+                prefix,
+            } => {
+                let old = self.variable(name)?;
+                let new = self.assign(name, old.wrapping_add(*delta))?;
+                Ok(if *prefix { new } else { old })
+            }
+        }
+    }
+}
+
+fn apply(operator: &str, left: i64, right: i64) -> anyhow::Result<i64> {
+    Ok(match operator {
+        "," => right,
+        "|" => left | right,
+        "^" => left ^ right,
+        "&" => left & right,
+        "==" => i64::from(left == right),
+        "!=" => i64::from(left != right),
+        "<" => i64::from(left < right),
+        ">" => i64::from(left > right),
+        "<=" => i64::from(left <= right),
+        ">=" => i64::from(left >= right),
+        "<<" => left.wrapping_shl(right as u32),
+        ">>" => left.wrapping_shr(right as u32),
+        "+" => left.wrapping_add(right),
+        "-" => left.wrapping_sub(right),
+        "*" => left.wrapping_mul(right),
+        "/" | "%" if right == 0 => return Err(anyhow!("division by 0")),
+        "/" => left.wrapping_div(right),
+        "%" => left.wrapping_rem(right),
+        "**" if right < 0 => return Err(anyhow!("exponent less than 0")),
+        "**" => left.wrapping_pow(u32::try_from(right).unwrap_or(u32::MAX)),
+        _ => unreachable!("unknown arithmetic operator {operator}"),
+    })
+}
+
+/// Evaluates an arithmetic expression, as in `$((expression))`, reading and
+/// assigning variables in `variables`.
+pub fn evaluate(expression: &str, variables: &mut Variables) -> anyhow::Result<i64> {
+    Evaluator {
+        variables,
+        depth: 0,
+    }
+    .evaluate_str(expression)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expression: &str) -> i64 {
+        evaluate(expression, &mut Variables::default()).unwrap()
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(eval("1 + 2 * 3"), 7);
+        assert_eq!(eval("(1 + 2) * 3"), 9);
+        assert_eq!(eval("2 ** 3 ** 2"), 512);
+        assert_eq!(eval("-2 ** 2"), 4);
+        assert_eq!(eval("7 / 2 + 7 % 2"), 4);
+        assert_eq!(eval("1 << 4 | 1 & 3 ^ 2"), 19);
+        assert_eq!(eval("!0 + ~0"), 0);
+        assert_eq!(eval("1 < 2 && 2 <= 2 || 0"), 1);
+        assert_eq!(eval("3 == 3 ? 4 : 5"), 4);
+        assert_eq!(eval("0 ? 1 : 0 ? 2 : 3"), 3);
+        assert_eq!(eval(""), 0);
+    }
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(eval("0x1f"), 31);
+        assert_eq!(eval("017"), 15);
+        assert_eq!(eval("2#101"), 5);
+        assert_eq!(eval("36#z"), 35);
+        assert_eq!(eval("64#_"), 63);
+        assert!(evaluate("08", &mut Variables::default()).is_err());
+        assert!(evaluate("2#2", &mut Variables::default()).is_err());
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut variables = Variables::default();
+        variables.set("a", "5".into()).unwrap();
+        variables.set("b", "a * 2".into()).unwrap();
+
+        assert_eq!(evaluate("a + b + unset", &mut variables).unwrap(), 15);
+        assert_eq!(evaluate("c = a++, c", &mut variables).unwrap(), 5);
+        assert_eq!(variables.value("a"), Some("6"));
+        assert_eq!(evaluate("--a", &mut variables).unwrap(), 5);
+        assert_eq!(evaluate("a++ + ++a", &mut variables).unwrap(), 12);
+        assert_eq!(evaluate("a = 5", &mut variables).unwrap(), 5);
+        assert_eq!(evaluate("c += 10", &mut variables).unwrap(), 15);
+        assert_eq!(evaluate("c <<= 1", &mut variables).unwrap(), 30);
+        assert_eq!(variables.value("c"), Some("30"));
+
+        variables.set("loop", "loop".into()).unwrap();
+        assert!(evaluate("loop", &mut variables).is_err());
+    }
+
+    #[test]
+    fn test_short_circuit() {
+        let mut variables = Variables::default();
+        assert_eq!(evaluate("0 && (a = 1)", &mut variables).unwrap(), 0);
+        assert_eq!(evaluate("1 || (a = 1)", &mut variables).unwrap(), 1);
+        assert_eq!(evaluate("1 ? 2 : (a = 1)", &mut variables).unwrap(), 2);
+        assert_eq!(variables.value("a"), None);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |expression| {
+            evaluate(expression, &mut Variables::default())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(error("1 / 0"), "division by 0");
+        assert_eq!(error("2 ** -1"), "exponent less than 0");
+        assert_eq!(
+            error("1 +"),
+            "1 +: syntax error: operand expected (error token is \"\")"
+        );
+        assert!(error("(1").contains("`)' expected"));
+        assert!(error("1 2").contains("invalid arithmetic operator"));
+        assert!(error("1 $ 2").contains("invalid arithmetic operator"));
+    }
+}
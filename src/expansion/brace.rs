@@ -0,0 +1,210 @@
+/// Performs brace expansion on a word as written in the input, so that
+/// `a{b,c}d` becomes `abd acd` and `{1..3}` becomes `1 2 3`.
+///
+/// Braces that are quoted, escaped or part of another expansion are left
+/// untouched, as are the ones that do not form a valid expression.
+pub fn expand_braces(word: &str) -> Vec<String> {
+    let chars: Vec<char> = word.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] != '{' {
+            i = skip(&chars, i);
+            continue;
+        }
+
+        if let Some((close, commas)) = find_close(&chars, i) {
+            let preamble: String = chars[..i].iter().collect();
+            let postscript: String = chars[close + 1..].iter().collect();
+
+            let alternatives = if commas.is_empty() {
+                let body: String = chars[i + 1..close].iter().collect();
+                sequence(&body)
+            } else {
+                let mut bounds = vec![i];
+                bounds.extend(&commas);
+                bounds.push(close);
+                Some(
+                    bounds
+                        .windows(2)
+                        .map(|pair| chars[pair[0] + 1..pair[1]].iter().collect())
+                        .collect(),
+                )
+            };
+
+            if let Some(alternatives) = alternatives {
+                return alternatives
+                    .into_iter()
+                    .flat_map(|alternative| expand_braces(&format!("{alternative}{postscript}")))
+                    .map(|expanded| format!("{preamble}{expanded}"))
+                    .collect();
+            }
+        }
+        i += 1;
+    }
+
+    vec![word.to_string()]
+}
+
+/// Returns the index following the quoted string, escape or expansion
+/// starting at `i`, or `i + 1` for any other character.
+fn skip(chars: &[char], i: usize) -> usize {
+    match chars[i] {
+        '\\' => i + 2,
+        '\'' => skip_until(chars, i + 1, '\'', false),
+        '"' => skip_until(chars, i + 1, '"', true),
+        '`' => skip_until(chars, i + 1, '`', true),
+        '$' | '<' | '>' if matches!(chars.get(i + 1), Some('(' | '{')) => {
+            let (open, close) = if chars[i + 1] == '(' {
+                ('(', ')')
+            } else {
+                ('{', '}')
+            };
+            let mut depth = 0;
+            let mut j = i + 1;
+            while j < chars.len() {
+                if chars[j] == open {
+                    depth += 1;
+                } else if chars[j] == close {
+                    depth -= 1;
+                    if depth == 0 {
+                        return j + 1;
+                    }
+                }
+                j = if chars[j] == open || chars[j] == close {
+                    j + 1
+                } else {
+                    skip(chars, j)
+                };
+            }
+            j
+        }
+        _ => i + 1,
+    }
+}
+
+fn skip_until(chars: &[char], mut i: usize, end: char, escapes: bool) -> usize {
+    while i < chars.len() && chars[i] != end {
+        i += if escapes && chars[i] == '\\' { 2 } else { 1 };
+    }
+    i + 1
+}
+
+/// Finds the brace closing the one at `open`, along with the commas that
+/// are directly inside it.
+fn find_close(chars: &[char], open: usize) -> Option<(usize, Vec<usize>)> {
+    let mut depth = 0;
+    let mut commas = Vec::new();
+    let mut i = open;
+
+    while i < chars.len() {
+        match chars[i] {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((i, commas));
+                }
+            }
+            ',' if depth == 1 => commas.push(i),
+            _ => {
+                i = skip(chars, i);
+                continue;
+            }
+        }
+        i += 1;
+    }
+
+    None
+}
+
+/// Expands a sequence expression such as `1..10`, `a..z..2` or `01..10`.
+fn sequence(body: &str) -> Option<Vec<String>> {
+    let parts: Vec<&str> = body.split("..").collect();
+    let (start, end, step) = match parts[..] {
+        [start, end] => (start, end, 1),
+        [start, end, step] => (start, end, step.parse::<i64>().ok()?.unsigned_abs().max(1)),
+        _ => return None,
+    };
+
+    if let (Ok(first), Ok(last)) = (start.parse::<i64>(), end.parse::<i64>()) {
+        let padded = |bound: &str| {
+            let digits = bound.trim_start_matches('-');
+            digits.len() > 1 && digits.starts_with('0')
+        };
+        let width = if padded(start) || padded(end) {
+            start.len().max(end.len())
+        } else {
+            0
+        };
+        return Some(
+            range(first, last, step)
+                .map(|n| format!("{n:0width$}"))
+                .collect(),
+        );
+    }
+
+    let (mut first, mut last) = (start.chars(), end.chars());
+    match (first.next(), first.next(), last.next(), last.next()) {
+        (Some(first), None, Some(last), None)
+            if first.is_ascii_alphabetic() && last.is_ascii_alphabetic() =>
+        {
+            Some(
+                range(first as i64, last as i64, step)
+                    .map(|c| char::from(c as u8).to_string())
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+fn range(first: i64, last: i64, step: u64) -> impl Iterator<Item = i64> {
+    let step = step as usize;
+    let (low, high) = (first.min(last), first.max(last));
+    let values: Vec<i64> = if first <= last {
+        (low..=high).step_by(step).collect()
+    } else {
+        (low..=high).rev().step_by(step).collect()
+    };
+    values.into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_braces(word: &str, expected: &[&str]) {
+        assert_eq!(expand_braces(word), expected);
+    }
+
+    #[test]
+    fn test_brace_lists() {
+        assert_braces("a{b,c}d", &["abd", "acd"]);
+        assert_braces("file{,.bak}", &["file", "file.bak"]);
+        assert_braces("{a,b}{1,2}", &["a1", "a2", "b1", "b2"]);
+        assert_braces("x{a,b{c,d}}y", &["xay", "xbcy", "xbdy"]);
+    }
+
+    #[test]
+    fn test_brace_sequences() {
+        assert_braces("{1..4}", &["1", "2", "3", "4"]);
+        assert_braces("{3..1}", &["3", "2", "1"]);
+        assert_braces("{1..10..4}", &["1", "5", "9"]);
+        assert_braces("{a..e..2}", &["a", "c", "e"]);
+        assert_braces("{08..11}", &["08", "09", "10", "11"]);
+        assert_braces("{-1..1}", &["-1", "0", "1"]);
+    }
+
+    #[test]
+    fn test_brace_untouched() {
+        assert_braces("{}", &["{}"]);
+        assert_braces("{a}", &["{a}"]);
+        assert_braces("{a,b", &["{a,b"]);
+        assert_braces("{1..b}", &["{1..b}"]);
+        assert_braces("'{a,b}'", &["'{a,b}'"]);
+        assert_braces("\\{a,b}", &["\\{a,b}"]);
+        assert_braces("${x}{a,b}", &["${x}a", "${x}b"]);
+        assert_braces("\"{a,b}\"{c,d}", &["\"{a,b}\"c", "\"{a,b}\"d"]);
+    }
+}
@@ -0,0 +1,203 @@
+use std::{fs, path::Path};
+
+use crate::shell::options::ShellOptions;
+
+use super::pattern::Pattern;
+
+/// Returns the sorted paths matching a pattern, in which `/` separates the
+/// components that are matched against the file names of each directory.
+pub fn glob(pattern: &str, options: &ShellOptions) -> Vec<String> {
+    let (mut paths, rest) = match pattern.strip_prefix('/') {
+        Some(rest) => (vec![String::from("/")], rest),
+        None => (vec![String::new()], pattern),
+    };
+
+    let components: Vec<&str> = rest.split('/').collect();
+    for (i, component) in components.iter().enumerate() {
+        let last = i == components.len() - 1;
+        paths = if component.is_empty() {
+            // A trailing slash only matches directories, and repeated
+            // slashes are kept as they are.
+            paths
+                .into_iter()
+                .filter(|path| !last || Path::new(path).is_dir())
+                .map(|path| path + "/")
+                .collect()
+        } else if *component == "**" && options.globstar {
+            let mut matches = Vec::new();
+            for path in &paths {
+                if !last {
+                    matches.push(path.clone());
+                }
+                walk(path, options.dotglob, last, &mut matches);
+            }
+            matches
+        } else {
+            let pattern = Pattern::new(component);
+            let mut matches = Vec::new();
+            for path in &paths {
+                if pattern.has_wildcards() {
+                    for name in read_names(path) {
+                        if pattern.matches_file_name(&name, options.dotglob) {
+                            let child = join(path, &name);
+                            if last || Path::new(&child).is_dir() {
+                                matches.push(child);
+                            }
+                        }
+                    }
+                } else {
+                    let child = join(path, &unescape(component));
+                    if !last || fs::symlink_metadata(&child).is_ok() {
+                        matches.push(child);
+                    }
+                }
+            }
+            matches
+        };
+
+        if paths.is_empty() {
+            break;
+        }
+    }
+
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+fn join(directory: &str, name: &str) -> String {
+    if directory.is_empty() || directory.ends_with('/') {
+        format!("{directory}{name}")
+    } else {
+        format!("{directory}/{name}")
+    }
+}
+
+/// The sorted names of the entries of a directory, `""` being the current
+/// one.
+fn read_names(directory: &str) -> Vec<String> {
+    let directory = if directory.is_empty() { "." } else { directory };
+    let Ok(entries) = fs::read_dir(directory) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Collects the subdirectories of `directory` recursively, along with the
+/// other files if `files` is set. Symbolic links are not followed.
+fn walk(directory: &str, dotglob: bool, files: bool, matches: &mut Vec<String>) {
+    for name in read_names(directory) {
+        if name.starts_with('.') && !dotglob {
+            continue;
+        }
+        let child = join(directory, &name);
+        let is_dir = fs::symlink_metadata(&child).is_ok_and(|metadata| metadata.is_dir());
+        if is_dir {
+            matches.push(child.clone());
+            walk(&child, dotglob, files, matches);
+        } else if files {
+            matches.push(child);
+        }
+    }
+}
+
+/// Removes the backslashes of a component without wildcards.
+fn unescape(component: &str) -> String {
+    let mut result = String::with_capacity(component.len());
+    let mut chars = component.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next().or(Some('\\'))),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// Creates a directory tree for a test, removed when dropped.
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str, files: &[&str]) -> Self {
+            let root =
+                std::env::temp_dir().join(format!("rjsh-glob-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            for file in files {
+                let path = root.join(file);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                if !file.ends_with('/') {
+                    fs::write(path, "").unwrap();
+                }
+            }
+            Self(root)
+        }
+
+        fn glob(&self, pattern: &str, options: &ShellOptions) -> Vec<String> {
+            let root = self.0.to_str().unwrap();
+            glob(&format!("{root}/{pattern}"), options)
+                .into_iter()
+                .map(|path| path[root.len() + 1..].to_string())
+                .collect()
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_glob_files() {
+        let dir = TestDir::new(
+            "files",
+            &["b.rs", "a.rs", "c.txt", ".hidden.rs", "sub/d.rs"],
+        );
+        let options = ShellOptions::default();
+        assert_eq!(dir.glob("*.rs", &options), vec!["a.rs", "b.rs"]);
+        assert_eq!(dir.glob("?.*", &options), vec!["a.rs", "b.rs", "c.txt"]);
+        assert_eq!(dir.glob("*/*.rs", &options), vec!["sub/d.rs"]);
+        assert_eq!(dir.glob("*/", &options), vec!["sub/"]);
+        assert_eq!(dir.glob("sub/*", &options), vec!["sub/d.rs"]);
+        assert!(dir.glob("*.md", &options).is_empty());
+
+        let options = ShellOptions {
+            dotglob: true,
+            ..ShellOptions::default()
+        };
+        assert_eq!(
+            dir.glob("*.rs", &options),
+            vec![".hidden.rs", "a.rs", "b.rs"]
+        );
+    }
+
+    #[test]
+    fn test_glob_globstar() {
+        let dir = TestDir::new("globstar", &["a.rs", "x/b.rs", "x/y/c.rs", "x/y/d.txt"]);
+        let options = ShellOptions {
+            globstar: true,
+            ..ShellOptions::default()
+        };
+        assert_eq!(
+            dir.glob("**/*.rs", &options),
+            vec!["a.rs", "x/b.rs", "x/y/c.rs"]
+        );
+        assert_eq!(
+            dir.glob("x/**", &options),
+            vec!["x/b.rs", "x/y", "x/y/c.rs", "x/y/d.txt"]
+        );
+
+        let options = ShellOptions::default();
+        assert_eq!(dir.glob("**/*.rs", &options), vec!["x/b.rs"]);
+    }
+}
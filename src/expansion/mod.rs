@@ -0,0 +1,962 @@
+use nix::unistd::{getuid, User};
+use pest::{
+    iterators::{Pair, Pairs},
+    Parser,
+};
+
+use crate::{
+    exec::{command_substitution, process_substitution},
+    parser::{alias::expand_aliases, parse_command, Rule, ShellParser},
+    shell::Shell,
+};
+
+use self::{brace::expand_braces, glob::glob, pattern::Pattern};
+
+pub mod arithmetic;
+pub mod brace;
+pub mod glob;
+pub mod pattern;
+
+const DEFAULT_IFS: &str = " \t\n";
+
+fn parse_word(word: &str) -> anyhow::Result<Pair<'_, Rule>> {
+    let mut pairs = ShellParser::parse(Rule::word, word)
+        .map_err(|e| anyhow::anyhow!("invalid word `{word}`: {e}"))?;
+    let pair = pairs.next().unwrap();
+    if pair.as_str().len() != word.len() {
+        return Err(anyhow::anyhow!("invalid word `{word}`"));
+    }
+    Ok(pair)
+}
+
+/// A piece of an expanded word, before field splitting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// Quoted text is taken literally, while `split` text comes from an
+    /// unquoted expansion and is subject to field splitting.
+    Text {
+        text: String,
+        quoted: bool,
+        split: bool,
+    },
+    /// A field boundary that does not come from field splitting, as in
+    /// `"$@"`.
+    FieldBreak,
+}
+
+impl Segment {
+    const fn text(text: String, quoted: bool, split: bool) -> Self {
+        Self::Text {
+            text,
+            quoted,
+            split,
+        }
+    }
+}
+
+/// A field resulting from field splitting, made of pieces of text that
+/// remember whether they were quoted.
+#[derive(Debug, Default)]
+struct Field {
+    pieces: Vec<(String, bool)>,
+    // Quoted empty strings still produce a field.
+    quoted: bool,
+}
+
+impl Field {
+    fn push(&mut self, text: &str, quoted: bool) {
+        self.quoted |= quoted;
+        match self.pieces.last_mut() {
+            Some((last, last_quoted)) if *last_quoted == quoted => last.push_str(text),
+            _ => self.pieces.push((text.to_string(), quoted)),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        !self.quoted && self.pieces.iter().all(|(text, _)| text.is_empty())
+    }
+
+    fn text(&self) -> String {
+        self.pieces.iter().map(|(text, _)| text.as_str()).collect()
+    }
+
+    /// The field as a pattern, where quoted pieces only match literally.
+    fn pattern(&self) -> String {
+        self.pieces
+            .iter()
+            .map(|(text, quoted)| {
+                if *quoted {
+                    pattern::escape(text)
+                } else {
+                    text.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Splits the segments of an expanded word into fields, using the
+/// characters of `ifs` as delimiters.
+fn split_fields(segments: Vec<Segment>, ifs: &str) -> Vec<Field> {
+    let mut fields = Vec::new();
+    let mut field = Field::default();
+    // Whether the previous character was IFS whitespace that ended a field,
+    // in which case a following non-whitespace delimiter is part of the same
+    // separator.
+    let mut after_whitespace = false;
+
+    for segment in segments {
+        match segment {
+            Segment::FieldBreak => {
+                fields.push(std::mem::take(&mut field));
+                after_whitespace = false;
+            }
+            Segment::Text {
+                text,
+                quoted,
+                split: false,
+            } => {
+                field.push(&text, quoted);
+                after_whitespace = false;
+            }
+            Segment::Text { text, .. } => {
+                for c in text.chars() {
+                    if !ifs.contains(c) {
+                        field.push(c.encode_utf8(&mut [0; 4]), false);
+                        after_whitespace = false;
+                    } else if c.is_whitespace() {
+                        if !field.is_empty() {
+                            fields.push(std::mem::take(&mut field));
+                            after_whitespace = true;
+                        }
+                    } else {
+                        if !field.is_empty() || !after_whitespace {
+                            fields.push(std::mem::take(&mut field));
+                        }
+                        after_whitespace = false;
+                    }
+                }
+            }
+        }
+    }
+
+    if !field.is_empty() {
+        fields.push(field);
+    }
+
+    fields
+}
+
+fn join_segments(segments: Vec<Segment>) -> String {
+    segments
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text { text, .. } => text,
+            Segment::FieldBreak => String::from(" "),
+        })
+        .collect()
+}
+
+/// Turns segments into a pattern where quoted text only matches literally.
+fn segments_to_pattern(segments: Vec<Segment>) -> String {
+    segments
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text {
+                text, quoted: true, ..
+            } => pattern::escape(&text),
+            Segment::Text { text, .. } => text,
+            Segment::FieldBreak => String::from(" "),
+        })
+        .collect()
+}
+
+struct Expander<'a> {
+    shell: &'a mut dyn Shell,
+}
+
+impl Expander<'_> {
+    fn expand(&mut self, word: &str) -> anyhow::Result<Vec<Segment>> {
+        let mut segments = Vec::new();
+        let word = match self.expand_tilde(word) {
+            Some((home, rest)) => {
+                segments.push(Segment::text(home, true, false));
+                rest
+            }
+            None => word,
+        };
+        if !word.is_empty() {
+            self.expand_parts(parse_word(word)?.into_inner(), false, &mut segments)?;
+        }
+        Ok(segments)
+    }
+
+    /// Expands the unquoted tilde prefix of a word, up to the first `/`.
+    /// Returns the expanded prefix and the rest of the word.
+    fn expand_tilde<'w>(&self, word: &'w str) -> Option<(String, &'w str)> {
+        let end = word.find('/').unwrap_or(word.len());
+        let user = word[..end].strip_prefix('~')?;
+        let directory = match user {
+            "" => self.shell.get_var("HOME").or_else(|| {
+                let user = User::from_uid(getuid()).ok()??;
+                Some(user.dir.to_string_lossy().into_owned())
+            })?,
+            "+" => self.shell.get_var("PWD")?,
+            "-" => self.shell.get_var("OLDPWD")?,
+            user if user
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')) =>
+            {
+                let user = User::from_name(user).ok()??;
+                user.dir.to_string_lossy().into_owned()
+            }
+            _ => return None,
+        };
+        Some((directory, &word[end..]))
+    }
+
+    fn expand_parts(
+        &mut self,
+        parts: Pairs<'_, Rule>,
+        quoted: bool,
+        segments: &mut Vec<Segment>,
+    ) -> anyhow::Result<()> {
+        for part in parts {
+            match part.as_rule() {
+                Rule::single_quoted => {
+                    let content = part.into_inner().next().unwrap().as_str();
+                    segments.push(Segment::text(content.to_string(), true, false));
+                }
+                Rule::ansi_c_quoted => {
+                    let content = part.into_inner().next().unwrap().as_str();
+                    segments.push(Segment::text(decode_ansi_c(content), true, false));
+                }
+                Rule::double_quoted => {
+                    let inner = part.into_inner();
+                    if inner.clone().next().is_none() {
+                        segments.push(Segment::text(String::new(), true, false));
+                    }
+                    self.expand_parts(inner, true, segments)?;
+                }
+                Rule::double_escaped | Rule::escaped | Rule::heredoc_escaped => {
+                    let mut text = String::new();
+                    push_escaped(&mut text, part.as_str());
+                    segments.push(Segment::text(text, true, false));
+                }
+                Rule::double_literal | Rule::heredoc_literal => {
+                    segments.push(Segment::text(part.as_str().to_string(), true, false));
+                }
+                Rule::param_literal | Rule::replace_literal => {
+                    segments.push(Segment::text(part.as_str().to_string(), quoted, !quoted));
+                }
+                Rule::simple_param | Rule::braced_param => {
+                    self.expand_parameter(part, quoted, segments)?;
+                }
+                Rule::arith_subst => {
+                    let expression = part.into_inner().next().unwrap().as_str();
+                    let value = expand_arithmetic(self.shell, expression)?;
+                    segments.push(Segment::text(value.to_string(), quoted, !quoted));
+                }
+                Rule::command_subst => {
+                    let body = part.into_inner().next().unwrap().as_str();
+                    let output = self.substitute(body)?;
+                    segments.push(Segment::text(output, quoted, !quoted));
+                }
+                Rule::process_subst => {
+                    let mut inner = part.into_inner();
+                    let output = inner.next().unwrap().as_str().starts_with('>');
+                    let body = inner.next().unwrap().as_str();
+                    let list = parse_command(body).map_err(|e| anyhow::anyhow!("{e}"))?;
+                    let path = process_substitution(self.shell, &list, output)?;
+                    segments.push(Segment::text(path, true, false));
+                }
+                Rule::backtick_subst => {
+                    let body = unescape_backticks(part.into_inner().next().unwrap().as_str());
+                    // Unlike `$(...)`, the body is only parsed now, with the
+                    // aliases defined by then.
+                    let body = expand_aliases(&body, self.shell.aliases());
+                    let output = self.substitute(&body)?;
+                    segments.push(Segment::text(output, quoted, !quoted));
+                }
+                _ => segments.push(Segment::text(part.as_str().to_string(), quoted, false)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the body of a command substitution and returns its output.
+    fn substitute(&mut self, body: &str) -> anyhow::Result<String> {
+        if body.trim().is_empty() {
+            return Ok(String::new());
+        }
+        let list = parse_command(body).map_err(|e| anyhow::anyhow!("{e}"))?;
+        let (output, code) = command_substitution(self.shell, &list)?;
+        self.shell.set_last_exit_code(code);
+        Ok(output)
+    }
+
+    /// Expands a word nested in a parameter expansion as a single string.
+    fn expand_to_string(&mut self, word: Option<Pair<'_, Rule>>) -> anyhow::Result<String> {
+        let mut segments = Vec::new();
+        if let Some(word) = word {
+            self.expand_parts(word.into_inner(), true, &mut segments)?;
+        }
+        Ok(join_segments(segments))
+    }
+
+    fn expand_to_pattern(&mut self, word: Option<Pair<'_, Rule>>) -> anyhow::Result<Pattern> {
+        let mut segments = Vec::new();
+        if let Some(word) = word {
+            self.expand_parts(word.into_inner(), false, &mut segments)?;
+        }
+        Ok(Pattern::new(&segments_to_pattern(segments)))
+    }
+
+    fn lookup(&self, name: &str) -> Option<String> {
+        match name {
+            "?" => Some(self.shell.last_exit_code().to_string()),
+            "$" => Some(self.shell.pid().to_string()),
+            "!" => self.shell.last_background_pid().map(|pid| pid.to_string()),
+            "#" => Some(self.shell.positional_parameters().len().to_string()),
+            "-" => Some(self.shell.options().flags()),
+            "0" => Some(self.shell.name().to_string()),
+            "@" | "*" => {
+                let parameters = self.shell.positional_parameters();
+                (!parameters.is_empty()).then(|| parameters.join(" "))
+            }
+            _ => match name.parse::<usize>() {
+                // `${00}` is `$0` as well.
+                Ok(0) => Some(self.shell.name().to_string()),
+                Ok(n) => self.shell.positional_parameters().get(n - 1).cloned(),
+                Err(_) => self.shell.get_var(name),
+            },
+        }
+    }
+
+    fn ifs(&self) -> String {
+        self.shell
+            .get_var("IFS")
+            .unwrap_or_else(|| DEFAULT_IFS.to_string())
+    }
+
+    fn push_value(&self, value: Option<String>, quoted: bool, segments: &mut Vec<Segment>) {
+        segments.push(Segment::text(value.unwrap_or_default(), quoted, !quoted));
+    }
+
+    /// Expands `$@` and `$*`, which produce one field per positional
+    /// parameter unless `$*` is quoted.
+    fn push_positional_parameters(&self, name: &str, quoted: bool, segments: &mut Vec<Segment>) {
+        let parameters = self.shell.positional_parameters();
+        if quoted && name == "*" {
+            let separator = self.ifs().chars().next().map(String::from);
+            let joined = parameters.join(separator.as_deref().unwrap_or(""));
+            segments.push(Segment::text(joined, true, false));
+            return;
+        }
+
+        for (i, parameter) in parameters.iter().enumerate() {
+            if i > 0 {
+                segments.push(Segment::FieldBreak);
+            }
+            segments.push(Segment::text(parameter.clone(), quoted, !quoted));
+        }
+    }
+
+    fn expand_parameter(
+        &mut self,
+        pair: Pair<'_, Rule>,
+        quoted: bool,
+        segments: &mut Vec<Segment>,
+    ) -> anyhow::Result<()> {
+        let braced = pair.as_rule() == Rule::braced_param;
+        let mut inner = pair.into_inner();
+        let first = inner.next().unwrap();
+
+        if first.as_rule() == Rule::param_length {
+            let name = first.into_inner().next().unwrap().as_str();
+            let length = match name {
+                "@" | "*" => self.shell.positional_parameters().len(),
+                _ => self.lookup(name).unwrap_or_default().chars().count(),
+            };
+            segments.push(Segment::text(length.to_string(), quoted, !quoted));
+            return Ok(());
+        }
+
+        let name = if braced {
+            first.into_inner().next().unwrap().as_str()
+        } else {
+            first.as_str()
+        };
+
+        let Some(operation) = inner.next() else {
+            if name == "@" || name == "*" {
+                self.push_positional_parameters(name, quoted, segments);
+            } else {
+                self.push_value(self.lookup(name), quoted, segments);
+            }
+            return Ok(());
+        };
+
+        let value = self.lookup(name);
+        if operation.as_rule() == Rule::param_replace {
+            let replaced = self.replace(value.unwrap_or_default(), operation)?;
+            segments.push(Segment::text(replaced, quoted, !quoted));
+            return Ok(());
+        }
+
+        let mut inner = operation.into_inner();
+        let op = inner.next().unwrap().as_str();
+        let word = inner.next();
+        let unset = match &value {
+            None => true,
+            Some(value) => op.starts_with(':') && value.is_empty(),
+        };
+
+        match op.trim_start_matches(':') {
+            "-" => {
+                if unset {
+                    if let Some(word) = word {
+                        self.expand_parts(word.into_inner(), quoted, segments)?;
+                    }
+                } else {
+                    self.push_value(value, quoted, segments);
+                }
+            }
+            "=" => {
+                if unset {
+                    if name.parse::<usize>().is_ok() || !is_name(name) {
+                        return Err(anyhow::anyhow!("${name}: cannot assign in this way"));
+                    }
+                    let new_value = self.expand_to_string(word)?;
+                    self.shell.set_var(name, new_value.clone())?;
+                    self.push_value(Some(new_value), quoted, segments);
+                } else {
+                    self.push_value(value, quoted, segments);
+                }
+            }
+            "?" => {
+                if unset {
+                    let message = self.expand_to_string(word)?;
+                    // A shell running a script does not go on after it.
+                    if !self.shell.options().interactive {
+                        self.shell.exit();
+                    }
+                    if message.is_empty() {
+                        return Err(anyhow::anyhow!("{name}: parameter null or not set"));
+                    }
+                    return Err(anyhow::anyhow!("{name}: {message}"));
+                }
+                self.push_value(value, quoted, segments);
+            }
+            "+" => {
+                if unset {
+                    self.push_value(None, quoted, segments);
+                } else if let Some(word) = word {
+                    self.expand_parts(word.into_inner(), quoted, segments)?;
+                }
+            }
+            _ => {
+                let value = value.unwrap_or_default();
+                let pattern = self.expand_to_pattern(word)?;
+                let longest = op.len() == 2;
+                let result = if op.starts_with('#') {
+                    match pattern.match_prefix(&value, longest) {
+                        Some(end) => value[end..].to_string(),
+                        None => value,
+                    }
+                } else {
+                    match pattern.match_suffix(&value, longest) {
+                        Some(start) => value[..start].to_string(),
+                        None => value,
+                    }
+                };
+                segments.push(Segment::text(result, quoted, !quoted));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Performs `${VAR/pattern/replacement}` and its `//`, `/#` and `/%`
+    /// variants.
+    fn replace(&mut self, value: String, operation: Pair<'_, Rule>) -> anyhow::Result<String> {
+        let mut kind = None;
+        let mut pattern = None;
+        let mut replacement = None;
+        for inner in operation.into_inner() {
+            match inner.as_rule() {
+                Rule::replace_kind => kind = Some(inner.as_str()),
+                Rule::replace_pattern => pattern = Some(inner),
+                _ => replacement = Some(inner),
+            }
+        }
+
+        if pattern.as_ref().is_none_or(|p| p.as_str().is_empty()) {
+            return Ok(value);
+        }
+        let pattern = self.expand_to_pattern(pattern)?;
+        let replacement = self.expand_to_string(replacement)?;
+
+        let result = match kind {
+            Some("#") => match pattern.match_prefix(&value, true) {
+                Some(end) => format!("{replacement}{}", &value[end..]),
+                None => value,
+            },
+            Some("%") => match pattern.match_suffix(&value, true) {
+                Some(start) => format!("{}{replacement}", &value[..start]),
+                None => value,
+            },
+            _ => {
+                let all = kind == Some("/");
+                let mut result = String::new();
+                let mut rest = value.as_str();
+                let mut replaced = false;
+                while !rest.is_empty() {
+                    match pattern.match_prefix(rest, true) {
+                        Some(end) if end > 0 && (all || !replaced) => {
+                            result.push_str(&replacement);
+                            rest = &rest[end..];
+                            replaced = true;
+                        }
+                        _ => {
+                            let c = rest.chars().next().unwrap();
+                            result.push(c);
+                            rest = &rest[c.len_utf8()..];
+                        }
+                    }
+                }
+                result
+            }
+        };
+
+        Ok(result)
+    }
+}
+
+/// Removes the backslashes quoting `$`, `` ` `` and `\\` in the body of a
+/// backtick substitution.
+fn unescape_backticks(body: &str) -> String {
+    let mut result = String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' && matches!(chars.peek(), Some('$' | '`' | '\\')) {
+            result.push(chars.next().unwrap());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Whether `name` is a valid variable name.
+pub fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Expands words into the fields of a command: parameter expansion, field
+/// splitting and quote removal.
+pub fn expand_words(shell: &mut dyn Shell, words: &[String]) -> anyhow::Result<Vec<String>> {
+    let mut expander = Expander { shell };
+    let mut result = Vec::new();
+
+    for word in words.iter().flat_map(|word| expand_braces(word)) {
+        let segments = expander.expand(&word)?;
+        for field in split_fields(segments, &expander.ifs()) {
+            result.extend(expand_pathname(expander.shell, &field)?);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Replaces a field containing unquoted wildcards with the paths it
+/// matches, following the `nullglob` and `failglob` options when there are
+/// none.
+fn expand_pathname(shell: &dyn Shell, field: &Field) -> anyhow::Result<Vec<String>> {
+    let pattern = field.pattern();
+    if !Pattern::new(&pattern).has_wildcards() {
+        return Ok(vec![field.text()]);
+    }
+
+    let options = shell.options();
+    let matches = glob(&pattern, options);
+    if !matches.is_empty() {
+        Ok(matches)
+    } else if options.failglob {
+        Err(anyhow::anyhow!("no match: {}", field.text()))
+    } else if options.nullglob {
+        Ok(Vec::new())
+    } else {
+        Ok(vec![field.text()])
+    }
+}
+
+/// Expands a word without field splitting, as for redirection targets.
+pub fn expand_word(shell: &mut dyn Shell, word: &str) -> anyhow::Result<String> {
+    let mut expander = Expander { shell };
+    Ok(join_segments(expander.expand(word)?))
+}
+
+/// Whether expanding a word runs a command substitution.
+pub fn has_command_substitution(word: &str) -> bool {
+    ShellParser::parse(Rule::word, word).is_ok_and(|pairs| {
+        pairs
+            .flatten()
+            .any(|pair| matches!(pair.as_rule(), Rule::command_subst | Rule::backtick_subst))
+    })
+}
+
+/// Expands a word into a pattern where only the unquoted wildcards are
+/// special, as for the patterns of `case`.
+pub fn expand_pattern(shell: &mut dyn Shell, word: &str) -> anyhow::Result<Pattern> {
+    let mut expander = Expander { shell };
+    Ok(Pattern::new(&segments_to_pattern(expander.expand(word)?)))
+}
+
+/// Expands the parameters and substitutions of an arithmetic expression,
+/// then evaluates it.
+pub fn expand_arithmetic(shell: &mut dyn Shell, expression: &str) -> anyhow::Result<i64> {
+    let expression = expand_heredoc(shell, expression)?;
+    arithmetic::evaluate(&expression, shell.variables_mut())
+}
+
+/// Expands the body of a here-document, in which quotes are not special.
+pub fn expand_heredoc(shell: &mut dyn Shell, body: &str) -> anyhow::Result<String> {
+    let pair = ShellParser::parse(Rule::heredoc_body, body)
+        .map_err(|e| anyhow::anyhow!("invalid here-document: {e}"))?
+        .next()
+        .unwrap();
+    let mut expander = Expander { shell };
+    let mut segments = Vec::new();
+    expander.expand_parts(pair.into_inner(), true, &mut segments)?;
+    Ok(join_segments(segments))
+}
+
+/// Performs quote removal on a word as it was written in the input.
+pub fn remove_quotes(word: &str) -> anyhow::Result<String> {
+    let mut result = String::new();
+
+    for part in parse_word(word)?.into_inner() {
+        match part.as_rule() {
+            Rule::single_quoted => result.push_str(part.into_inner().next().unwrap().as_str()),
+            Rule::ansi_c_quoted => {
+                result.push_str(&decode_ansi_c(part.into_inner().next().unwrap().as_str()));
+            }
+            Rule::double_quoted => {
+                for inner in part.into_inner() {
+                    match inner.as_rule() {
+                        Rule::double_escaped => push_escaped(&mut result, inner.as_str()),
+                        _ => result.push_str(inner.as_str()),
+                    }
+                }
+            }
+            Rule::escaped => push_escaped(&mut result, part.as_str()),
+            _ => result.push_str(part.as_str()),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Pushes the character following a backslash, dropping escaped newlines
+/// which are line continuations.
+fn push_escaped(result: &mut String, escaped: &str) {
+    let c = &escaped[1..];
+    if c != "\n" && c != "\r\n" {
+        result.push_str(c);
+    }
+}
+
+/// Decodes the escape sequences of a `$'...'` string.
+fn decode_ansi_c(content: &str) -> String {
+    let mut result = String::new();
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        let Some(escape) = chars.next() else {
+            result.push('\\');
+            break;
+        };
+        match escape {
+            'a' => result.push('\x07'),
+            'b' => result.push('\x08'),
+            'e' | 'E' => result.push('\x1b'),
+            'f' => result.push('\x0c'),
+            'n' => result.push('\n'),
+            'r' => result.push('\r'),
+            't' => result.push('\t'),
+            'v' => result.push('\x0b'),
+            '\\' | '\'' | '"' | '?' => result.push(escape),
+            '0'..='7' => {
+                let mut value = escape.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            value = value * 8 + digit;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                result.extend(char::from_u32(value));
+            }
+            'x' | 'u' | 'U' => {
+                let max_digits = match escape {
+                    'x' => 2,
+                    'u' => 4,
+                    _ => 8,
+                };
+                let mut value = 0;
+                let mut digits = 0;
+                while digits < max_digits {
+                    match chars.peek().and_then(|c| c.to_digit(16)) {
+                        Some(digit) => {
+                            value = value * 16 + digit;
+                            digits += 1;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                if digits == 0 {
+                    result.push('\\');
+                    result.push(escape);
+                } else {
+                    result.extend(char::from_u32(value));
+                }
+            }
+            'c' => match chars.next() {
+                Some(control) => result.extend(char::from_u32(control as u32 & 0x1f)),
+                None => result.push_str("\\c"),
+            },
+            _ => {
+                result.push('\\');
+                result.push(escape);
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shell::DefaultShell;
+
+    use super::*;
+
+    fn expand_with(vars: &[(&str, &str)], word: &str) -> Vec<String> {
+        let mut shell = DefaultShell::default();
+        for (name, value) in vars {
+            shell.set_var(name, value.to_string()).unwrap();
+        }
+        expand_words(&mut shell, &[word.to_string()]).unwrap()
+    }
+
+    fn assert_unquoted(word: &str, expected: &str) {
+        assert_eq!(remove_quotes(word).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_remove_quotes_unquoted() {
+        assert_unquoted("abc", "abc");
+        assert_unquoted("a$b", "a$b");
+    }
+
+    #[test]
+    fn test_remove_quotes_single_quotes() {
+        assert_unquoted("'hello world'", "hello world");
+        assert_unquoted("a'b'c", "abc");
+        assert_unquoted("'a\\b'", "a\\b");
+        assert_unquoted("''", "");
+    }
+
+    #[test]
+    fn test_remove_quotes_double_quotes() {
+        assert_unquoted("\"hello world\"", "hello world");
+        assert_unquoted("\"a'b\"", "a'b");
+        assert_unquoted("\"a\\\"b\"", "a\"b");
+        assert_unquoted("\"a\\nb\"", "a\\nb");
+        assert_unquoted("\"a\\\\b\"", "a\\b");
+    }
+
+    #[test]
+    fn test_remove_quotes_backslash() {
+        assert_unquoted("a\\ b", "a b");
+        assert_unquoted("\\'", "'");
+        assert_unquoted("a\\\nb", "ab");
+    }
+
+    #[test]
+    fn test_remove_quotes_ansi_c() {
+        assert_unquoted("$'a\\tb'", "a\tb");
+        assert_unquoted("$'\\x41\\101\\u00e9'", "AAé");
+        assert_unquoted("$'it\\'s'", "it's");
+        assert_unquoted("$'\\cA'", "\x01");
+    }
+
+    #[test]
+    fn test_expand_simple_parameters() {
+        let vars = [("RJSH_TEST_A", "hello"), ("RJSH_TEST_EMPTY", "")];
+        assert_eq!(expand_with(&vars, "$RJSH_TEST_A"), vec!["hello"]);
+        assert_eq!(expand_with(&vars, "x${RJSH_TEST_A}y"), vec!["xhelloy"]);
+        assert_eq!(expand_with(&vars, "'$RJSH_TEST_A'"), vec!["$RJSH_TEST_A"]);
+        assert_eq!(expand_with(&vars, "\\$RJSH_TEST_A"), vec!["$RJSH_TEST_A"]);
+        assert_eq!(expand_with(&vars, "$RJSH_TEST_EMPTY"), Vec::<String>::new());
+        assert_eq!(expand_with(&vars, "\"$RJSH_TEST_EMPTY\""), vec![""]);
+        assert_eq!(expand_with(&vars, "$"), vec!["$"]);
+        assert_eq!(expand_with(&vars, "$?"), vec!["0"]);
+    }
+
+    #[test]
+    fn test_expand_positional_parameters() {
+        let mut shell = DefaultShell::default();
+        shell.set_name(String::from("rjsh"));
+        shell.set_positional_parameters(vec![String::from("a"), String::from("b")]);
+        let words: Vec<String> = ["$0", "${00}", "${#00}", "$2", "${3}"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            expand_words(&mut shell, &words).unwrap(),
+            vec!["rjsh", "rjsh", "4", "b"]
+        );
+    }
+
+    #[test]
+    fn test_expand_tilde() {
+        let vars = [("HOME", "/home/rjsh"), ("PWD", "/tmp")];
+        assert_eq!(expand_with(&vars, "~"), vec!["/home/rjsh"]);
+        assert_eq!(expand_with(&vars, "~/'a b'"), vec!["/home/rjsh/a b"]);
+        assert_eq!(expand_with(&vars, "~+/x"), vec!["/tmp/x"]);
+        assert_eq!(expand_with(&vars, "~root"), vec!["/root"]);
+        assert_eq!(expand_with(&vars, "'~'"), vec!["~"]);
+        assert_eq!(expand_with(&vars, "a~"), vec!["a~"]);
+        assert_eq!(
+            expand_with(&vars, "~rjsh_no_such_user/x"),
+            vec!["~rjsh_no_such_user/x"]
+        );
+    }
+
+    #[test]
+    fn test_expand_heredoc() {
+        let mut shell = DefaultShell::default();
+        shell.set_var("RJSH_TEST_DOC", "v".into()).unwrap();
+        let body = "a \"$RJSH_TEST_DOC\" '$(echo x)'\n\\$ \\n $\n";
+        assert_eq!(
+            expand_heredoc(&mut shell, body).unwrap(),
+            "a \"v\" 'x'\n$ \\n $\n"
+        );
+    }
+
+    #[test]
+    fn test_expand_field_splitting() {
+        let vars = [("RJSH_TEST_SPLIT", "  a  b c ")];
+        assert_eq!(expand_with(&vars, "$RJSH_TEST_SPLIT"), vec!["a", "b", "c"]);
+        assert_eq!(
+            expand_with(&vars, "\"$RJSH_TEST_SPLIT\""),
+            vec!["  a  b c "]
+        );
+        assert_eq!(
+            expand_with(&vars, "x${RJSH_TEST_SPLIT}y"),
+            vec!["x", "a", "b", "c", "y"]
+        );
+
+        let segments = vec![Segment::text(":a::b :".into(), false, true)];
+        let fields: Vec<String> = split_fields(segments, ": ")
+            .iter()
+            .map(Field::text)
+            .collect();
+        assert_eq!(fields, vec!["", "a", "", "b"]);
+    }
+
+    #[test]
+    fn test_expand_default_operators() {
+        let vars = [("RJSH_TEST_SET", "v"), ("RJSH_TEST_NULL", "")];
+        assert_eq!(expand_with(&vars, "${RJSH_TEST_SET:-d}"), vec!["v"]);
+        assert_eq!(expand_with(&vars, "${RJSH_TEST_NULL:-d}"), vec!["d"]);
+        assert_eq!(
+            expand_with(&vars, "${RJSH_TEST_NULL-d}"),
+            Vec::<String>::new()
+        );
+        assert_eq!(expand_with(&vars, "${RJSH_TEST_UNSET-d e}"), vec!["d", "e"]);
+        assert_eq!(
+            expand_with(&vars, "\"${RJSH_TEST_UNSET-d e}\""),
+            vec!["d e"]
+        );
+        assert_eq!(expand_with(&vars, "${RJSH_TEST_SET:+alt}"), vec!["alt"]);
+        assert_eq!(
+            expand_with(&vars, "${RJSH_TEST_NULL:+alt}"),
+            Vec::<String>::new()
+        );
+        assert_eq!(expand_with(&vars, "${RJSH_TEST_NULL+alt}"), vec!["alt"]);
+        assert_eq!(expand_with(&vars, "${#RJSH_TEST_SET}"), vec!["1"]);
+    }
+
+    #[test]
+    fn test_expand_assign_and_error_operators() {
+        let mut shell = DefaultShell::default();
+        let words = ["${RJSH_TEST_ASSIGN:=new}".to_string()];
+        assert_eq!(expand_words(&mut shell, &words).unwrap(), vec!["new"]);
+        assert_eq!(shell.get_var("RJSH_TEST_ASSIGN").as_deref(), Some("new"));
+
+        let words = ["${RJSH_TEST_MISSING:?oops}".to_string()];
+        let error = expand_words(&mut shell, &words).unwrap_err();
+        assert_eq!(error.to_string(), "RJSH_TEST_MISSING: oops");
+        assert!(shell.should_exit());
+
+        let mut shell = DefaultShell::default();
+        shell.options_mut().interactive = true;
+        assert!(expand_words(&mut shell, &words).is_err());
+        assert!(!shell.should_exit());
+    }
+
+    #[test]
+    fn test_expand_pattern_operators() {
+        let vars = [("RJSH_TEST_PATH", "/usr/lib/file.tar.gz")];
+        assert_eq!(
+            expand_with(&vars, "${RJSH_TEST_PATH#*/}"),
+            vec!["usr/lib/file.tar.gz"]
+        );
+        assert_eq!(
+            expand_with(&vars, "${RJSH_TEST_PATH##*/}"),
+            vec!["file.tar.gz"]
+        );
+        assert_eq!(
+            expand_with(&vars, "${RJSH_TEST_PATH%.*}"),
+            vec!["/usr/lib/file.tar"]
+        );
+        assert_eq!(
+            expand_with(&vars, "${RJSH_TEST_PATH%%.*}"),
+            vec!["/usr/lib/file"]
+        );
+        assert_eq!(
+            expand_with(&vars, "${RJSH_TEST_PATH%'.*'}"),
+            vec!["/usr/lib/file.tar.gz"]
+        );
+        assert_eq!(
+            expand_with(&vars, "${RJSH_TEST_PATH/lib/bin}"),
+            vec!["/usr/bin/file.tar.gz"]
+        );
+        assert_eq!(
+            expand_with(&vars, "${RJSH_TEST_PATH//\\//:}"),
+            vec![":usr:lib:file.tar.gz"]
+        );
+        assert_eq!(
+            expand_with(&vars, "${RJSH_TEST_PATH/#\\/usr/~}"),
+            vec!["~/lib/file.tar.gz"]
+        );
+        assert_eq!(
+            expand_with(&vars, "${RJSH_TEST_PATH/%gz/xz}"),
+            vec!["/usr/lib/file.tar.xz"]
+        );
+    }
+}
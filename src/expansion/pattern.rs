@@ -0,0 +1,356 @@
+/// A shell pattern, as used by `${VAR#pattern}`, `case` and pathname
+/// expansion.
+///
+/// Backslashes escape the following character, which is how quoted parts of
+/// a word end up matching literally.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    tokens: Vec<PatternToken>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PatternToken {
+    Char(char),
+    AnyChar,
+    AnyString,
+    Bracket(Bracket),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bracket {
+    negated: bool,
+    items: Vec<BracketItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BracketItem {
+    Char(char),
+    Range(char, char),
+    Class(CharClass),
+}
+
+/// The character classes usable in brackets, such as `[[:alpha:]]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Alnum,
+    Alpha,
+    Blank,
+    Cntrl,
+    Digit,
+    Graph,
+    Lower,
+    Print,
+    Punct,
+    Space,
+    Upper,
+    Xdigit,
+}
+
+impl CharClass {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "alnum" => Self::Alnum,
+            "alpha" => Self::Alpha,
+            "blank" => Self::Blank,
+            "cntrl" => Self::Cntrl,
+            "digit" => Self::Digit,
+            "graph" => Self::Graph,
+            "lower" => Self::Lower,
+            "print" => Self::Print,
+            "punct" => Self::Punct,
+            "space" => Self::Space,
+            "upper" => Self::Upper,
+            "xdigit" => Self::Xdigit,
+            _ => return None,
+        })
+    }
+
+    fn matches(self, c: char) -> bool {
+        match self {
+            Self::Alnum => c.is_alphanumeric(),
+            Self::Alpha => c.is_alphabetic(),
+            Self::Blank => c == ' ' || c == '\t',
+            Self::Cntrl => c.is_control(),
+            Self::Digit => c.is_ascii_digit(),
+            Self::Graph => !c.is_control() && !c.is_whitespace(),
+            Self::Lower => c.is_lowercase(),
+            Self::Print => !c.is_control(),
+            Self::Punct => c.is_ascii_punctuation(),
+            Self::Space => c.is_whitespace(),
+            Self::Upper => c.is_uppercase(),
+            Self::Xdigit => c.is_ascii_hexdigit(),
+        }
+    }
+}
+
+impl Bracket {
+    fn matches(&self, c: char) -> bool {
+        let found = self.items.iter().any(|item| match item {
+            BracketItem::Char(expected) => *expected == c,
+            BracketItem::Range(start, end) => (*start..=*end).contains(&c),
+            BracketItem::Class(class) => class.matches(c),
+        });
+        found != self.negated
+    }
+
+    /// Parses a bracket expression starting right after the `[`. Returns the
+    /// bracket and the number of characters consumed, including the closing
+    /// `]`, or `None` if the bracket is not terminated.
+    fn parse(chars: &[char]) -> Option<(Self, usize)> {
+        let mut i = 0;
+        let mut negated = false;
+        if matches!(chars.first(), Some('!' | '^')) {
+            negated = true;
+            i += 1;
+        }
+
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let mut c = *chars.get(i)?;
+            if c == ']' && !first {
+                return Some((Self { negated, items }, i + 1));
+            }
+            first = false;
+            if c == '[' && chars.get(i + 1) == Some(&':') {
+                let class: String = chars[i + 2..].iter().collect();
+                if let Some((name, _)) = class.split_once(":]") {
+                    if let Some(class) = CharClass::from_name(name) {
+                        items.push(BracketItem::Class(class));
+                        i += name.chars().count() + 4;
+                        continue;
+                    }
+                }
+            }
+            if c == '\\' {
+                i += 1;
+                c = *chars.get(i)?;
+            }
+            i += 1;
+
+            if chars.get(i) == Some(&'-') && chars.get(i + 1).is_some_and(|c| *c != ']') {
+                let mut end = chars[i + 1];
+                i += 2;
+                if end == '\\' {
+                    end = *chars.get(i)?;
+                    i += 1;
+                }
+                items.push(BracketItem::Range(c, end));
+            } else {
+                items.push(BracketItem::Char(c));
+            }
+        }
+    }
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Self {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '\\' if i + 1 < chars.len() => {
+                    tokens.push(PatternToken::Char(chars[i + 1]));
+                    i += 2;
+                }
+                '?' => {
+                    tokens.push(PatternToken::AnyChar);
+                    i += 1;
+                }
+                '*' => {
+                    // Consecutive stars are equivalent to a single one.
+                    if tokens.last() != Some(&PatternToken::AnyString) {
+                        tokens.push(PatternToken::AnyString);
+                    }
+                    i += 1;
+                }
+                '[' => match Bracket::parse(&chars[i + 1..]) {
+                    Some((bracket, len)) => {
+                        tokens.push(PatternToken::Bracket(bracket));
+                        i += len + 1;
+                    }
+                    None => {
+                        tokens.push(PatternToken::Char('['));
+                        i += 1;
+                    }
+                },
+                c => {
+                    tokens.push(PatternToken::Char(c));
+                    i += 1;
+                }
+            }
+        }
+
+        Self { tokens }
+    }
+
+    /// Whether the pattern contains any special character, as opposed to
+    /// only matching a literal string.
+    pub fn has_wildcards(&self) -> bool {
+        self.tokens
+            .iter()
+            .any(|token| !matches!(token, PatternToken::Char(_)))
+    }
+
+    /// Matches a file name, whose leading `.` has to be matched explicitly
+    /// unless `dotglob` is set.
+    pub fn matches_file_name(&self, name: &str, dotglob: bool) -> bool {
+        if name.starts_with('.')
+            && !dotglob
+            && self.tokens.first() != Some(&PatternToken::Char('.'))
+        {
+            return false;
+        }
+        self.matches(name)
+    }
+
+    pub fn matches(&self, s: &str) -> bool {
+        let chars: Vec<char> = s.chars().collect();
+        self.matches_chars(&chars)
+    }
+
+    fn matches_chars(&self, chars: &[char]) -> bool {
+        let (mut t, mut c) = (0, 0);
+        // Position of the last `*` and of the input it started matching at,
+        // to backtrack when the rest of the pattern fails.
+        let mut backtrack: Option<(usize, usize)> = None;
+
+        while c < chars.len() {
+            let matched = match self.tokens.get(t) {
+                Some(PatternToken::AnyString) => {
+                    backtrack = Some((t, c));
+                    t += 1;
+                    continue;
+                }
+                Some(PatternToken::Char(expected)) => *expected == chars[c],
+                Some(PatternToken::AnyChar) => true,
+                Some(PatternToken::Bracket(bracket)) => bracket.matches(chars[c]),
+                None => false,
+            };
+
+            if matched {
+                t += 1;
+                c += 1;
+            } else if let Some((star, start)) = backtrack {
+                t = star + 1;
+                c = start + 1;
+                backtrack = Some((star, start + 1));
+            } else {
+                return false;
+            }
+        }
+
+        self.tokens[t..]
+            .iter()
+            .all(|token| *token == PatternToken::AnyString)
+    }
+
+    /// Returns the length in bytes of the shortest or longest prefix of `s`
+    /// matching the pattern.
+    pub fn match_prefix(&self, s: &str, longest: bool) -> Option<usize> {
+        let mut ends: Vec<usize> = s.char_indices().map(|(i, _)| i).collect();
+        ends.push(s.len());
+        if longest {
+            ends.reverse();
+        }
+        ends.into_iter().find(|end| self.matches(&s[..*end]))
+    }
+
+    /// Returns the byte offset of the shortest or longest suffix of `s`
+    /// matching the pattern.
+    pub fn match_suffix(&self, s: &str, longest: bool) -> Option<usize> {
+        let mut starts: Vec<usize> = s.char_indices().map(|(i, _)| i).collect();
+        starts.push(s.len());
+        if !longest {
+            starts.reverse();
+        }
+        starts.into_iter().find(|start| self.matches(&s[*start..]))
+    }
+}
+
+/// Escapes the characters that have a special meaning in patterns.
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | '*' | '?' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_literal() {
+        assert!(Pattern::new("abc").matches("abc"));
+        assert!(!Pattern::new("abc").matches("abd"));
+        assert!(!Pattern::new("abc").matches("abcd"));
+        assert!(Pattern::new("").matches(""));
+    }
+
+    #[test]
+    fn test_pattern_wildcards() {
+        assert!(Pattern::new("*").matches(""));
+        assert!(Pattern::new("a*").matches("abc"));
+        assert!(Pattern::new("*c").matches("abc"));
+        assert!(Pattern::new("a*b*c").matches("aXbYbZc"));
+        assert!(!Pattern::new("a*b*c").matches("aXbYbZ"));
+        assert!(Pattern::new("a?c").matches("abc"));
+        assert!(!Pattern::new("a?c").matches("ac"));
+    }
+
+    #[test]
+    fn test_pattern_brackets() {
+        assert!(Pattern::new("[abc]").matches("b"));
+        assert!(!Pattern::new("[abc]").matches("d"));
+        assert!(Pattern::new("[a-c]x").matches("bx"));
+        assert!(Pattern::new("[!a-c]").matches("d"));
+        assert!(!Pattern::new("[^a-c]").matches("a"));
+        assert!(Pattern::new("[]a]").matches("]"));
+        assert!(Pattern::new("[a-]").matches("-"));
+        assert!(Pattern::new("[").matches("["));
+    }
+
+    #[test]
+    fn test_pattern_classes() {
+        assert!(Pattern::new("[[:alpha:]]*").matches("abc1"));
+        assert!(!Pattern::new("[[:alpha:]]*").matches("1abc"));
+        assert!(Pattern::new("[![:digit:]]").matches("x"));
+        assert!(Pattern::new("[[:upper:][:digit:]_]").matches("_"));
+        assert!(Pattern::new("[[:xdigit:]]").matches("F"));
+        assert!(!Pattern::new("[[:space:]]").matches("a"));
+    }
+
+    #[test]
+    fn test_pattern_file_names() {
+        assert!(!Pattern::new("*").matches_file_name(".hidden", false));
+        assert!(Pattern::new("*").matches_file_name(".hidden", true));
+        assert!(Pattern::new(".*").matches_file_name(".hidden", false));
+        assert!(!Pattern::new("?hidden").matches_file_name(".hidden", false));
+        assert!(Pattern::new("*").matches_file_name("visible", false));
+    }
+
+    #[test]
+    fn test_pattern_escapes() {
+        assert!(Pattern::new("\\*").matches("*"));
+        assert!(!Pattern::new("\\*").matches("a"));
+        assert!(Pattern::new(&escape("a*[b]?")).matches("a*[b]?"));
+    }
+
+    #[test]
+    fn test_pattern_prefix_suffix() {
+        let pattern = Pattern::new("*/");
+        assert_eq!(pattern.match_prefix("a/b/c", false), Some(2));
+        assert_eq!(pattern.match_prefix("a/b/c", true), Some(4));
+        let pattern = Pattern::new(".*");
+        assert_eq!(pattern.match_suffix("a.tar.gz", false), Some(5));
+        assert_eq!(pattern.match_suffix("a.tar.gz", true), Some(1));
+        assert_eq!(Pattern::new("x").match_suffix("abc", true), None);
+    }
+}
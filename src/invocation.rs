@@ -0,0 +1,163 @@
+use anyhow::anyhow;
+
+/// Where the shell reads its commands from.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Input {
+    /// The string given with `-c`.
+    Command(String),
+    Script(String),
+    Stdin,
+}
+
+/// The command line the shell was started with.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Invocation {
+    pub input: Input,
+    /// The name of the shell or script, `$0`.
+    pub name: String,
+    /// The positional parameters.
+    pub arguments: Vec<String>,
+    /// Whether `-i` asked for an interactive shell.
+    pub interactive: bool,
+    /// Login shells are started with `-l` or a name starting with `-`.
+    pub login: bool,
+    /// `--norc` skips the `rjshrc` files.
+    pub norc: bool,
+    /// `--noprofile` skips the profile files of login shells.
+    pub noprofile: bool,
+}
+
+impl Invocation {
+    /// Parses the arguments of the shell, starting with its own name:
+    /// `rjsh [--login] [--norc] [--noprofile] [-ils] [-c command [name
+    /// [argument...]] | script [argument...]]`.
+    pub fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut args = args.iter();
+        let mut name = args.next().cloned().unwrap_or_else(|| String::from("rjsh"));
+        let (mut command, mut stdin, mut interactive) = (false, false, false);
+        let mut login = name.starts_with('-');
+        let (mut norc, mut noprofile) = (false, false);
+        let mut operands = Vec::new();
+
+        for arg in args.by_ref() {
+            match arg.as_str() {
+                "--" | "-" => break,
+                "--login" => login = true,
+                "--norc" => norc = true,
+                "--noprofile" => noprofile = true,
+                _ if arg.starts_with("--") => return Err(anyhow!("{arg}: invalid option")),
+                _ => match arg.strip_prefix('-') {
+                    Some(flags) => {
+                        for flag in flags.chars() {
+                            match flag {
+                                'c' => command = true,
+                                's' => stdin = true,
+                                'i' => interactive = true,
+                                'l' => login = true,
+                                _ => return Err(anyhow!("-{flag}: invalid option")),
+                            }
+                        }
+                    }
+                    None => {
+                        operands.push(arg.clone());
+                        break;
+                    }
+                },
+            }
+        }
+        operands.extend(args.cloned());
+        let mut operands = operands.into_iter();
+
+        let input = if command {
+            let command = operands
+                .next()
+                .ok_or_else(|| anyhow!("-c: option requires an argument"))?;
+            if let Some(operand) = operands.next() {
+                name = operand;
+            }
+            Input::Command(command)
+        } else if stdin {
+            Input::Stdin
+        } else if let Some(script) = operands.next() {
+            name.clone_from(&script);
+            Input::Script(script)
+        } else {
+            Input::Stdin
+        };
+
+        Ok(Self {
+            input,
+            name,
+            arguments: operands.collect(),
+            interactive,
+            login,
+            norc,
+            noprofile,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> anyhow::Result<Invocation> {
+        let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+        Invocation::parse(&args)
+    }
+
+    #[test]
+    fn test_parse_command() {
+        let invocation = parse(&["rjsh", "-c", "echo $0 $1", "name", "a", "-b"]).unwrap();
+        assert_eq!(invocation.input, Input::Command("echo $0 $1".into()));
+        assert_eq!(invocation.name, "name");
+        assert_eq!(invocation.arguments, ["a", "-b"]);
+
+        let invocation = parse(&["rjsh", "-ic", "true"]).unwrap();
+        assert_eq!(invocation.name, "rjsh");
+        assert!(invocation.interactive);
+
+        assert!(parse(&["rjsh", "-c"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let invocation = parse(&["rjsh", "script.sh", "-i", "x"]).unwrap();
+        assert_eq!(invocation.input, Input::Script("script.sh".into()));
+        assert_eq!(invocation.name, "script.sh");
+        assert_eq!(invocation.arguments, ["-i", "x"]);
+        assert!(!invocation.interactive);
+
+        let invocation = parse(&["rjsh", "--", "-script"]).unwrap();
+        assert_eq!(invocation.input, Input::Script("-script".into()));
+    }
+
+    #[test]
+    fn test_parse_stdin() {
+        let invocation = parse(&["rjsh"]).unwrap();
+        assert_eq!(invocation.input, Input::Stdin);
+        assert!(invocation.arguments.is_empty());
+
+        let invocation = parse(&["rjsh", "-s", "a", "b"]).unwrap();
+        assert_eq!(invocation.input, Input::Stdin);
+        assert_eq!(invocation.name, "rjsh");
+        assert_eq!(invocation.arguments, ["a", "b"]);
+
+        assert_eq!(
+            parse(&["rjsh", "-x"]).unwrap_err().to_string(),
+            "-x: invalid option"
+        );
+    }
+
+    #[test]
+    fn test_parse_startup_options() {
+        let invocation = parse(&["rjsh", "--norc", "--noprofile", "-l"]).unwrap();
+        assert!(invocation.login && invocation.norc && invocation.noprofile);
+
+        let invocation = parse(&["-rjsh"]).unwrap();
+        assert!(invocation.login && !invocation.norc && !invocation.noprofile);
+        assert!(parse(&["rjsh", "--login"]).unwrap().login);
+        assert!(!parse(&["rjsh", "--", "--login"]).unwrap().login);
+        assert!(parse(&["rjsh", "--rc"]).is_err());
+    }
+}
@@ -2,7 +2,12 @@ pub mod builtins;
 pub mod editor;
 pub mod error;
 pub mod exec;
+pub mod expansion;
+pub mod invocation;
 pub mod parser;
 pub mod proc;
 pub mod prompt;
+pub mod script;
 pub mod shell;
+#[cfg(test)]
+mod test_util;
@@ -1,11 +1,23 @@
+use std::fs::File;
+use std::io::{BufReader, IsTerminal};
+use std::mem::ManuallyDrop;
+use std::os::fd::FromRawFd;
+use std::path::{Path, PathBuf};
+
+use nix::errno::Errno;
 use rjsh::editor::RjshEditor;
-use rjsh::exec::execute_command;
-use rjsh::parser::parse_command;
+use rjsh::exec::execute_list;
+use rjsh::expansion::expand_word;
+use rjsh::invocation::{Input, Invocation};
+use rjsh::parser::{is_blank, parse_command_with_aliases};
+use rjsh::proc::terminal;
 use rjsh::prompt::get_prompt;
-use rjsh::shell::Shell;
+use rjsh::script::{run_file, run_script};
+use rjsh::shell::{DefaultShell, Shell};
 use rustyline::error::ReadlineError;
 
-fn main() -> anyhow::Result<()> {
+/// Reads commands from the terminal until `exit` or the end of the input.
+fn run_interactive(shell: &mut DefaultShell) -> anyhow::Result<()> {
     let mut rl = RjshEditor::new()?;
 
     let home_dir = std::env::var("HOME")?;
@@ -13,32 +25,27 @@ fn main() -> anyhow::Result<()> {
     if rl.load_history(&history_path).is_err() {
         std::fs::File::create(&history_path)?;
     }
-    let mut shell = rjsh::shell::DefaultShell::default();
 
     while !shell.should_exit() {
         shell.update_jobs();
-        let prompt = get_prompt(&shell).unwrap_or_else(|_| String::from("$ "));
-        let readline = rl.readline(prompt.as_str());
+        let prompt = get_prompt(shell).unwrap_or_else(|_| String::from("$ "));
+        let continuation_prompt = shell.get_var("PS2").unwrap_or_else(|| String::from("> "));
+        let readline = rl.read_command(&prompt, &continuation_prompt);
         match readline {
             Ok(line) => {
-                if line.trim() == "" {
+                if is_blank(&line) {
                     continue;
                 }
-                match parse_command(line.as_str()) {
-                    Ok(command) => {
-                        if !command.name.is_empty() {
-                            let name = command.name.clone();
-
-                            match execute_command(&mut shell, command) {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    eprintln!("rjsh: {e}");
-                                }
-                            }
-
-                            if name != "exit" {
-                                rl.add_history_entry(line)?;
-                            }
+                match parse_command_with_aliases(line.as_str(), shell.aliases()) {
+                    Ok(list) => {
+                        execute_list(shell, &list);
+                        // SIGINT stopped the commands run by the shell.
+                        if terminal::take_interrupt() {
+                            shell.set_last_exit_code(130);
+                        }
+
+                        if !shell.should_exit() {
+                            rl.add_history_entry(line)?;
                         }
                     }
                     Err(e) => eprintln!("{e}"),
@@ -55,6 +62,101 @@ fn main() -> anyhow::Result<()> {
     }
 
     rl.save_history(&history_path)?;
+    Ok(())
+}
+
+/// Runs the startup files: the profiles of login shells, then the rc files
+/// of interactive shells, or the file named by `$RJSH_ENV` for the others.
+fn run_startup_files(shell: &mut DefaultShell, invocation: &Invocation) {
+    let interactive = shell.options().interactive;
+    let home = shell.get_var("HOME");
+    let in_home = |name: &str| home.as_ref().map(|home| Path::new(home).join(name));
+
+    // The files to run, with whether they can be missing.
+    let mut files: Vec<(PathBuf, bool)> = Vec::new();
+    if invocation.login && !invocation.noprofile {
+        files.push((PathBuf::from("/etc/profile"), true));
+        files.extend(in_home(".rjsh_profile").map(|path| (path, true)));
+    }
+    if interactive && !invocation.norc {
+        files.push((PathBuf::from("/etc/rjshrc"), true));
+        files.extend(in_home(".rjshrc").map(|path| (path, true)));
+    }
+    if !interactive {
+        match shell
+            .get_var("RJSH_ENV")
+            .map(|env| expand_word(shell, &env))
+        {
+            Some(Ok(path)) if !path.is_empty() => files.push((PathBuf::from(path), false)),
+            Some(Err(e)) => eprintln!("rjsh: RJSH_ENV: {e}"),
+            _ => {}
+        }
+    }
+
+    for (file, optional) in files {
+        if shell.should_exit() {
+            break;
+        }
+        if optional && !file.exists() {
+            continue;
+        }
+        if let Err(e) = run_file(shell, &file) {
+            eprintln!("rjsh: {e}");
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    let invocation = Invocation::parse(&args).unwrap_or_else(|e| {
+        eprintln!("rjsh: {e}");
+        std::process::exit(2);
+    });
+
+    let mut shell = DefaultShell::default();
+    shell.set_name(invocation.name.clone());
+    shell.set_positional_parameters(invocation.arguments.clone());
+    // Without a terminal there is nobody to prompt.
+    let interactive = invocation.interactive
+        || (invocation.input == Input::Stdin
+            && std::io::stdin().is_terminal()
+            && std::io::stderr().is_terminal());
+    shell.options_mut().interactive = interactive;
+    if interactive && terminal::is_available() {
+        match terminal::init() {
+            Ok(()) => shell.options_mut().monitor = true,
+            Err(e) => eprintln!("rjsh: cannot set up job control: {e}"),
+        }
+    }
+    run_startup_files(&mut shell, &invocation);
+
+    let code = match invocation.input {
+        Input::Command(command) => {
+            run_script(&mut shell, &mut command.as_bytes(), &invocation.name)
+        }
+        Input::Script(path) => match File::open(&path) {
+            Ok(file) => run_script(&mut shell, &mut BufReader::new(file), &path),
+            Err(e) => {
+                let errno = Errno::from_i32(e.raw_os_error().unwrap_or(0));
+                eprintln!("rjsh: {path}: {}", errno.desc());
+                if errno == Errno::ENOENT {
+                    127
+                } else {
+                    126
+                }
+            }
+        },
+        Input::Stdin if interactive => {
+            run_interactive(&mut shell)?;
+            shell.last_exit_code()
+        }
+        Input::Stdin => {
+            // Commands read from a pipe have to leave what follows them to
+            // the commands they run, so standard input is not buffered.
+            let mut stdin = ManuallyDrop::new(unsafe { File::from_raw_fd(0) });
+            run_script(&mut shell, &mut *stdin, "stdin")
+        }
+    };
 
-    std::process::exit(shell.last_exit_code());
+    std::process::exit(code);
 }
@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+
+use pest::Parser;
+
+use super::{Rule, ShellParser};
+
+/// The aliases of the shell, their values by name.
+pub type Aliases = BTreeMap<String, String>;
+
+/// Replaces the aliases used as command names in `text` with their values.
+///
+/// Values are expanded in turn, except for the aliases being expanded
+/// already, and a value ending with a blank has the word following the alias
+/// expanded as well.
+pub fn expand_aliases(text: &str, aliases: &Aliases) -> String {
+    expand(text, aliases, &mut Vec::new())
+}
+
+fn expand<'a>(text: &str, aliases: &'a Aliases, expanding: &mut Vec<&'a str>) -> String {
+    if aliases.is_empty() {
+        return text.to_string();
+    }
+    // Text that does not parse is left for the parser to report.
+    let Ok(pairs) = ShellParser::parse(Rule::command_line, text) else {
+        return text.to_string();
+    };
+
+    let mut replacements = Vec::new();
+    for command in pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::simple_command)
+    {
+        let words = command
+            .into_inner()
+            .filter(|pair| matches!(pair.as_rule(), Rule::name | Rule::arg));
+        for word in words {
+            let Some(value) = expand_word(word.as_str(), aliases, expanding) else {
+                break;
+            };
+            let next = value.ends_with([' ', '\t']);
+            let span = word.as_span();
+            replacements.push((span.start(), span.end(), value));
+            if !next {
+                break;
+            }
+        }
+    }
+
+    // Words replaced are plain names, so they never contain one another.
+    replacements.sort_by_key(|(start, _, _)| *start);
+    let mut expanded = String::new();
+    let mut last = 0;
+    for (start, end, value) in replacements {
+        expanded.push_str(&text[last..start]);
+        expanded.push_str(&value);
+        last = end;
+    }
+    expanded.push_str(&text[last..]);
+    expanded
+}
+
+/// The expanded value of the alias named `word`, if there is one and it is
+/// not being expanded already.
+fn expand_word<'a>(
+    word: &str,
+    aliases: &'a Aliases,
+    expanding: &mut Vec<&'a str>,
+) -> Option<String> {
+    let (name, value) = aliases.get_key_value(word)?;
+    if expanding.contains(&name.as_str()) {
+        return None;
+    }
+    expanding.push(name);
+    let value = expand(value, aliases, expanding);
+    expanding.pop();
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases(definitions: &[(&str, &str)]) -> Aliases {
+        definitions
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_expand_command_names() {
+        let aliases = aliases(&[("ll", "ls -la"), ("g", "grep")]);
+        assert_eq!(expand_aliases("ll /tmp", &aliases), "ls -la /tmp");
+        assert_eq!(
+            expand_aliases("ll | g ll && echo ll", &aliases),
+            "ls -la | grep ll && echo ll"
+        );
+        assert_eq!(expand_aliases("'ll'; \\ll", &aliases), "'ll'; \\ll");
+        assert_eq!(
+            expand_aliases("echo $(ll)\nif ll; then g; fi", &aliases),
+            "echo $(ls -la)\nif ls -la; then grep; fi"
+        );
+    }
+
+    #[test]
+    fn test_expand_recursively() {
+        let aliases = aliases(&[("ll", "ls -l"), ("ls", "ls -F"), ("a", "b"), ("b", "a")]);
+        assert_eq!(expand_aliases("ll x", &aliases), "ls -F -l x");
+        assert_eq!(expand_aliases("a", &aliases), "a");
+        assert_eq!(expand_aliases("b", &aliases), "b");
+    }
+
+    #[test]
+    fn test_expand_after_trailing_blank() {
+        let aliases = aliases(&[("sudo", "sudo "), ("ll", "ls -l"), ("x", "y")]);
+        assert_eq!(expand_aliases("sudo ll x", &aliases), "sudo  ls -l x");
+        assert_eq!(expand_aliases("sudo sudo x", &aliases), "sudo  sudo  y");
+        assert_eq!(expand_aliases("echo ll", &aliases), "echo ll");
+    }
+}
@@ -5,29 +5,66 @@ use crate::parser::token::Token;
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Redirectee {
     FileName(String),
+    /// The file descriptor to duplicate, as in `2>&1`.
     FileDescriptor(i32),
+    /// Closes the file descriptor, as in `2>&-`.
+    Close,
+    /// The word of `<&word` or `>&word`, which expands to the file
+    /// descriptor to duplicate or to `-`. Without a file descriptor number,
+    /// `>&file` stands for `&>file`.
+    Duplicate {
+        word: String,
+        fd_given: bool,
+    },
+    /// The body of a here-document, subject to expansion unless its
+    /// delimiter was quoted.
+    HereDoc {
+        body: String,
+        expand: bool,
+    },
+    /// The word of a here-string, `<<<word`, to expand.
+    HereString(String),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum RedirectionType {
-    Stdin,
-    Stdout,
-    Stderr,
+    Input,
+    Output,
+    ReadWrite,
+}
+
+impl RedirectionType {
+    /// The file descriptor redirected when the operator has no number.
+    pub const fn default_fd(self) -> i32 {
+        match self {
+            Self::Input | Self::ReadWrite => 0,
+            Self::Output => 1,
+        }
+    }
 }
 
 impl TryFrom<Token> for RedirectionType {
     type Error = String;
     fn try_from(value: Token) -> Result<Self, Self::Error> {
         match value {
-            Token::Langle => Ok(Self::Stdin),
-            Token::Rangle2 | Token::Rangle2F | Token::DoubleRangle2 => Ok(Self::Stderr),
-            Token::Rangle | Token::RangleF | Token::DoubleRangle => Ok(Self::Stdout),
+            Token::Langle
+            | Token::LangleAnd
+            | Token::DoubleLangle
+            | Token::DoubleLangleDash
+            | Token::TripleLangle => Ok(Self::Input),
+            Token::LangleRangle => Ok(Self::ReadWrite),
+            Token::Rangle
+            | Token::RangleF
+            | Token::DoubleRangle
+            | Token::RangleAnd
+            | Token::AndRangle
+            | Token::AndDoubleRangle => Ok(Self::Output),
             _ => Err("Invalid redirection marker".into()),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum RedirectionPermission {
     Truncate,
     Append,
@@ -37,41 +74,41 @@ impl TryFrom<Token> for RedirectionPermission {
     type Error = String;
     fn try_from(value: Token) -> Result<Self, Self::Error> {
         match value {
-            Token::Langle | Token::Rangle | Token::Rangle2 => Ok(Self::Standard),
-            Token::Rangle2F | Token::RangleF => Ok(Self::Truncate),
-            Token::DoubleRangle2 | Token::DoubleRangle => Ok(Self::Append),
+            Token::Langle
+            | Token::Rangle
+            | Token::DoubleLangle
+            | Token::DoubleLangleDash
+            | Token::TripleLangle
+            | Token::LangleRangle
+            | Token::LangleAnd
+            | Token::RangleAnd
+            | Token::AndRangle => Ok(Self::Standard),
+            Token::RangleF => Ok(Self::Truncate),
+            Token::DoubleRangle | Token::AndDoubleRangle => Ok(Self::Append),
             _ => Err("Invalid redirection marker".into()),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// A redirection of the file descriptor `fd`, applied in the order they
+/// appear on the command line.
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Redirection {
+    pub fd: i32,
     pub redirectee: Redirectee,
     pub type_: RedirectionType,
     pub permissions: RedirectionPermission,
 }
 
-impl TryFrom<(Token, String)> for Redirection {
-    type Error = String;
-    fn try_from((token, s): (Token, String)) -> Result<Self, Self::Error> {
-        let type_ = RedirectionType::try_from(token.clone())?;
-        let permissions = RedirectionPermission::try_from(token)?;
-        Ok(Self {
-            type_,
-            permissions,
-            redirectee: Redirectee::FileName(s),
-        })
-    }
-}
-
 impl Redirection {
     pub const fn new(
+        fd: i32,
         redirectee: Redirectee,
         type_: RedirectionType,
         permissions: RedirectionPermission,
     ) -> Self {
         Self {
+            fd,
             redirectee,
             type_,
             permissions,
@@ -79,17 +116,45 @@ impl Redirection {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct Command {
+/// A `NAME=value` word, whose value is expanded when it is assigned.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Assignment {
+    pub name: String,
+    pub value: String,
+}
+
+impl Display for Assignment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)
+    }
+}
+
+impl Assignment {
+    pub const fn new(name: String, value: String) -> Self {
+        Self { name, value }
+    }
+}
+
+/// A command with its arguments, or only assignments when its name is
+/// empty.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SimpleCommand {
+    /// The assignments before the name, which only apply to the command
+    /// if there is one.
+    pub assignments: Vec<Assignment>,
     pub name: String,
     pub args: Vec<String>,
     pub redirections: Vec<Redirection>,
-    pub background: bool,
 }
 
-impl Display for Command {
+impl Display for SimpleCommand {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{} ", self.name)?;
+        for assignment in &self.assignments {
+            write!(f, "{assignment} ")?;
+        }
+        if !self.name.is_empty() {
+            write!(f, "{} ", self.name)?;
+        }
         for arg in &self.args {
             write!(f, "{arg} ")?;
         }
@@ -97,18 +162,284 @@ impl Display for Command {
     }
 }
 
-impl Command {
-    pub const fn new(
-        name: String,
-        args: Vec<String>,
-        redirections: Vec<Redirection>,
-        background: bool,
-    ) -> Self {
+impl SimpleCommand {
+    pub const fn new(name: String, args: Vec<String>, redirections: Vec<Redirection>) -> Self {
         Self {
+            assignments: Vec::new(),
             name,
             args,
             redirections,
-            background,
         }
     }
+
+    pub fn with_assignments(mut self, assignments: Vec<Assignment>) -> Self {
+        self.assignments = assignments;
+        self
+    }
+}
+
+/// What `;;`, `;&` and `;;&` do after the body of a `case` item: stop,
+/// fall through to the next body, or keep testing the next patterns.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CaseTerminator {
+    Break,
+    FallThrough,
+    Continue,
+}
+
+impl Display for CaseTerminator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Break => write!(f, ";;"),
+            Self::FallThrough => write!(f, ";&"),
+            Self::Continue => write!(f, ";;&"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CaseItem {
+    pub patterns: Vec<String>,
+    pub body: List,
+    pub terminator: CaseTerminator,
+}
+
+impl CaseItem {
+    pub const fn new(patterns: Vec<String>, body: List, terminator: CaseTerminator) -> Self {
+        Self {
+            patterns,
+            body,
+            terminator,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CompoundCommand {
+    BraceGroup(List),
+    Subshell(List),
+    /// `((expression))`, expanded and evaluated when it runs.
+    Arithmetic(String),
+    /// The conditions and bodies of the `if` and `elif` branches, followed
+    /// by the `else` body.
+    If {
+        branches: Vec<(List, List)>,
+        else_body: Option<List>,
+    },
+    While {
+        condition: List,
+        body: List,
+    },
+    Until {
+        condition: List,
+        body: List,
+    },
+    /// Without `in`, the loop goes over the positional parameters.
+    For {
+        name: String,
+        words: Option<Vec<String>>,
+        body: List,
+    },
+    Case {
+        word: String,
+        items: Vec<CaseItem>,
+    },
+}
+
+impl Display for CompoundCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BraceGroup(list) => write!(f, "{{ {list}; }}"),
+            Self::Subshell(list) => write!(f, "({list})"),
+            Self::Arithmetic(expression) => write!(f, "(({expression}))"),
+            Self::If {
+                branches,
+                else_body,
+            } => {
+                for (i, (condition, body)) in branches.iter().enumerate() {
+                    let keyword = if i == 0 { "if" } else { "elif" };
+                    write!(f, "{keyword} {condition}; then {body}; ")?;
+                }
+                if let Some(body) = else_body {
+                    write!(f, "else {body}; ")?;
+                }
+                write!(f, "fi")
+            }
+            Self::While { condition, body } => write!(f, "while {condition}; do {body}; done"),
+            Self::Until { condition, body } => write!(f, "until {condition}; do {body}; done"),
+            Self::For { name, words, body } => {
+                write!(f, "for {name}")?;
+                if let Some(words) = words {
+                    write!(f, " in")?;
+                    for word in words {
+                        write!(f, " {word}")?;
+                    }
+                }
+                write!(f, "; do {body}; done")
+            }
+            Self::Case { word, items } => {
+                write!(f, "case {word} in ")?;
+                for item in items {
+                    write!(
+                        f,
+                        "{}) {} {} ",
+                        item.patterns.join("|"),
+                        item.body,
+                        item.terminator
+                    )?;
+                }
+                write!(f, "esac")
+            }
+        }
+    }
+}
+
+/// A function definition, whose redirections are performed each time the
+/// function is called.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FunctionDefinition {
+    pub name: String,
+    pub body: CompoundCommand,
+    pub redirections: Vec<Redirection>,
+}
+
+impl Display for FunctionDefinition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}() {}", self.name, self.body)
+    }
+}
+
+impl FunctionDefinition {
+    pub const fn new(name: String, body: CompoundCommand, redirections: Vec<Redirection>) -> Self {
+        Self {
+            name,
+            body,
+            redirections,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Command {
+    Simple(SimpleCommand),
+    /// A compound command with the redirections applying to all of it.
+    Compound(CompoundCommand, Vec<Redirection>),
+    Function(FunctionDefinition),
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Simple(command) => write!(f, "{command}"),
+            Self::Compound(command, _) => write!(f, "{command} "),
+            Self::Function(function) => write!(f, "{function} "),
+        }
+    }
+}
+
+impl From<SimpleCommand> for Command {
+    fn from(command: SimpleCommand) -> Self {
+        Self::Simple(command)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Pipeline {
+    pub commands: Vec<Command>,
+}
+
+impl Display for Pipeline {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, command) in self.commands.iter().enumerate() {
+            if i > 0 {
+                write!(f, "| ")?;
+            }
+            write!(f, "{command}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Pipeline {
+    pub const fn new(commands: Vec<Command>) -> Self {
+        Self { commands }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AndOrOperator {
+    And,
+    Or,
+}
+
+impl Display for AndOrOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::And => write!(f, "&&"),
+            Self::Or => write!(f, "||"),
+        }
+    }
+}
+
+/// Pipelines chained with `&&` and `||`, which are evaluated from left to
+/// right with short-circuiting.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AndOr {
+    pub first: Pipeline,
+    pub rest: Vec<(AndOrOperator, Pipeline)>,
+}
+
+impl Display for AndOr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.first)?;
+        for (operator, pipeline) in &self.rest {
+            write!(f, "{operator} {pipeline}")?;
+        }
+        Ok(())
+    }
+}
+
+impl AndOr {
+    pub const fn new(first: Pipeline, rest: Vec<(AndOrOperator, Pipeline)>) -> Self {
+        Self { first, rest }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ListItem {
+    pub and_or: AndOr,
+    pub background: bool,
+}
+
+impl ListItem {
+    pub const fn new(and_or: AndOr, background: bool) -> Self {
+        Self { and_or, background }
+    }
+}
+
+/// A sequence of and-or lists separated by `;`, `&` or newlines.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct List {
+    pub items: Vec<ListItem>,
+}
+
+impl Display for List {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, item) in self.items.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", item.and_or.to_string().trim_end())?;
+            if item.background {
+                write!(f, " &")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl List {
+    pub const fn new(items: Vec<ListItem>) -> Self {
+        Self { items }
+    }
 }
@@ -0,0 +1,252 @@
+use std::collections::VecDeque;
+
+/// The here-document operators found on a line, waiting for their bodies.
+struct Pending {
+    delimiter: String,
+    strip_tabs: bool,
+}
+
+/// The input with the bodies of its here-documents taken out, which are
+/// given in the order of their operators.
+pub struct Extracted {
+    pub text: String,
+    pub bodies: VecDeque<String>,
+    /// Whether the input ended before the delimiter of a here-document.
+    pub unterminated: bool,
+}
+
+/// Takes the bodies of the here-documents out of the input, as the lines
+/// following the one with their `<<` operator, up to their delimiter.
+pub fn extract(input: &str) -> Extracted {
+    let mut text = String::new();
+    let mut bodies = VecDeque::new();
+    let mut pending: VecDeque<Pending> = VecDeque::new();
+    let mut nesting = Vec::new();
+    let mut lines = input.split_inclusive('\n');
+
+    while let Some(line) = lines.next() {
+        text.push_str(line);
+        scan_line(line, &mut nesting, &mut pending);
+        if !nesting.is_empty() {
+            continue;
+        }
+
+        while let Some(heredoc) = pending.pop_front() {
+            let mut body = String::new();
+            let mut terminated = false;
+            for line in lines.by_ref() {
+                let line = if heredoc.strip_tabs {
+                    line.trim_start_matches('\t')
+                } else {
+                    line
+                };
+                if line.trim_end_matches('\n') == heredoc.delimiter {
+                    terminated = true;
+                    break;
+                }
+                body.push_str(line);
+                if !line.ends_with('\n') {
+                    body.push('\n');
+                }
+            }
+            bodies.push_back(body);
+            if !terminated {
+                return Extracted {
+                    text,
+                    bodies,
+                    unterminated: true,
+                };
+            }
+        }
+    }
+
+    Extracted {
+        text,
+        bodies,
+        unterminated: false,
+    }
+}
+
+/// Whether a here-document delimiter contains quoting, in which case the
+/// body is taken literally.
+pub fn is_quoted(delimiter: &str) -> bool {
+    delimiter.contains(['\'', '"', '\\'])
+}
+
+/// Scans a line for here-document operators, starting within `nesting` if
+/// the previous line ended inside quotes or parentheses. `nesting` holds the
+/// characters closing them, innermost last, and is left as the line ends.
+///
+/// Quoted text, command substitutions and arithmetic are skipped, so `<<`
+/// only starts a here-document outside of them.
+fn scan_line(line: &str, nesting: &mut Vec<char>, pending: &mut VecDeque<Pending>) {
+    let chars: Vec<char> = line.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1);
+        match nesting.last() {
+            Some(&end) if c == end => {
+                nesting.pop();
+            }
+            Some('\'') => {}
+            Some(_) if c == '\\' => i += 1,
+            Some('"') if c == '`' => nesting.push('`'),
+            Some('"') if c == '$' && next == Some(&'(') => {
+                nesting.push(')');
+                i += 1;
+            }
+            Some(')') if c == '\'' || c == '"' || c == '`' => nesting.push(c),
+            Some(')') if c == '(' => nesting.push(')'),
+            Some(_) => {}
+            None if c == '\\' => i += 1,
+            // The rest of the line is a comment, even if it has quotes.
+            None if c == '#'
+                && (i == 0 || matches!(chars[i - 1], ' ' | '\t' | ';' | '&' | '|' | '(' | ')')) =>
+            {
+                break
+            }
+            None if c == '\'' || c == '"' || c == '`' => nesting.push(c),
+            // `$(` and `$((` start with the first parenthesis, `((` takes
+            // both.
+            None if c == '$' && next == Some(&'(') => {
+                nesting.push(')');
+                i += 1;
+            }
+            None if c == '(' && next == Some(&'(') => {
+                nesting.extend([')', ')']);
+                i += 1;
+            }
+            None if c == '<' && next == Some(&'<') && chars.get(i + 2) != Some(&'<') => {
+                i += 2;
+                let strip_tabs = chars.get(i) == Some(&'-');
+                if strip_tabs {
+                    i += 1;
+                }
+                while matches!(chars.get(i), Some(' ' | '\t')) {
+                    i += 1;
+                }
+                let (word, end) = read_word(&chars, i);
+                pending.push_back(Pending {
+                    delimiter: unquote(&word),
+                    strip_tabs,
+                });
+                i = end;
+                continue;
+            }
+            None if c == '<' && next == Some(&'<') => i += 2,
+            None => {}
+        }
+        i += 1;
+    }
+}
+
+/// Reads the delimiter word starting at `start`, returning it with the
+/// index following it.
+fn read_word(chars: &[char], start: usize) -> (String, usize) {
+    let mut word = String::new();
+    let mut quote = None;
+    let mut i = start;
+
+    while let Some(&c) = chars.get(i) {
+        match quote {
+            Some(end) if c == end => quote = None,
+            Some(_) => {}
+            None if matches!(
+                c,
+                ' ' | '\t' | '\n' | ';' | '&' | '|' | '<' | '>' | '(' | ')'
+            ) =>
+            {
+                break
+            }
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == '\\' => {
+                word.push(c);
+                i += 1;
+                match chars.get(i) {
+                    Some(&c) => word.push(c),
+                    None => break,
+                }
+                i += 1;
+                continue;
+            }
+            None => {}
+        }
+        word.push(c);
+        i += 1;
+    }
+
+    (word, i)
+}
+
+/// Removes the quotes of a delimiter, which only matches the lines equal to
+/// its unquoted form.
+fn unquote(word: &str) -> String {
+    let mut result = String::new();
+    let mut quote = None;
+    let mut chars = word.chars();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(end) if c == end => quote = None,
+            Some(_) => result.push(c),
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == '\\' => result.extend(chars.next()),
+            None => result.push(c),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_heredocs() {
+        let extracted = extract("cat <<EOF <<-'END'\na $b\nEOF\n\tc\n\tEND\n");
+        assert_eq!(extracted.text, "cat <<EOF <<-'END'\n");
+        assert_eq!(extracted.bodies, ["a $b\n", "c\n"]);
+        assert!(!extracted.unterminated);
+    }
+
+    #[test]
+    fn test_extract_heredocs_ignores_quotes() {
+        let extracted = extract("echo '<<EOF' <<<x");
+        assert_eq!(extracted.text, "echo '<<EOF' <<<x");
+        assert!(extracted.bodies.is_empty());
+
+        let extracted = extract("a # don't <<EOF\nb");
+        assert_eq!(extracted.text, "a # don't <<EOF\nb");
+        assert!(extracted.bodies.is_empty());
+    }
+
+    #[test]
+    fn test_extract_heredocs_ignores_arithmetic() {
+        let input = "echo $((1<<2))\n((x <<= 2))\necho \"$(echo $((1 << 3)))\"\necho next\n";
+        let extracted = extract(input);
+        assert_eq!(extracted.text, input);
+        assert!(extracted.bodies.is_empty());
+        assert!(!extracted.unterminated);
+
+        let extracted = extract("(cat <<EOF\nbody\nEOF\n)");
+        assert_eq!(extracted.bodies, ["body\n"]);
+    }
+
+    #[test]
+    fn test_extract_unterminated_heredoc() {
+        let extracted = extract("cat <<EOF\nline");
+        assert_eq!(extracted.bodies, ["line\n"]);
+        assert!(extracted.unterminated);
+    }
+
+    #[test]
+    fn test_delimiter_quoting() {
+        assert_eq!(unquote("E\"O\"F"), "EOF");
+        assert_eq!(unquote("\\EOF"), "EOF");
+        assert!(is_quoted("'EOF'"));
+        assert!(!is_quoted("EOF"));
+    }
+}
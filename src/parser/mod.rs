@@ -1,58 +1,407 @@
+use std::collections::VecDeque;
+
+use pest::error::{ErrorVariant, InputLocation};
 use pest::iterators::Pair;
 use pest::Parser;
 use pest_derive::Parser;
 
-use crate::parser::ast::Command;
+use crate::parser::ast::{
+    AndOr, AndOrOperator, Assignment, CaseItem, CaseTerminator, Command, CompoundCommand,
+    FunctionDefinition, List, ListItem, Pipeline, SimpleCommand,
+};
 use crate::parser::token::Token;
 
-use self::ast::Redirection;
+use self::alias::{expand_aliases, Aliases};
+use self::ast::{Redirectee, Redirection, RedirectionPermission, RedirectionType};
 
+pub mod alias;
 pub mod ast;
+mod heredoc;
 mod token;
 
 #[derive(Parser)]
 #[grammar = "./src/parser/shell.pest"]
 pub struct ShellParser;
 
-pub fn parse_command(input: &str) -> Result<Command, String> {
-    let mut pairs = ShellParser::parse(Rule::command, input).map_err(|e| e.to_string())?;
+/// The bodies of the here-documents of the input being parsed.
+type HereDocs = VecDeque<String>;
+
+pub fn parse_command(input: &str) -> Result<List, String> {
+    parse_command_with_aliases(input, &Aliases::new())
+}
+
+/// Parses the input after expanding the `aliases` used in it.
+pub fn parse_command_with_aliases(input: &str, aliases: &Aliases) -> Result<List, String> {
+    let extracted = heredoc::extract(input);
+    let text = expand_aliases(&extracted.text, aliases);
+    let mut pairs = ShellParser::parse(Rule::command_line, &text).map_err(|e| e.to_string())?;
+
+    let list_pair = pairs.next().unwrap().into_inner().next().unwrap();
+    let mut heredocs = extracted.bodies;
+    build_list(list_pair, &mut heredocs)
+}
+
+/// Whether the input holds no command, only blank lines and comments.
+pub fn is_blank(input: &str) -> bool {
+    input.lines().all(|line| {
+        let line = line.trim_start();
+        line.is_empty() || line.starts_with('#')
+    })
+}
+
+/// Whether the input is incomplete and more lines have to be read before
+/// parsing it, as when a here-document is missing its delimiter or a
+/// compound command is not closed yet.
+pub fn needs_more_input(input: &str) -> bool {
+    let extracted = heredoc::extract(input);
+    if extracted.unterminated {
+        return true;
+    }
+    let text = extracted.text.trim_end();
+    if is_blank(text) {
+        return false;
+    }
+    match ShellParser::parse(Rule::command_line, text) {
+        Ok(_) => false,
+        // Expecting more at the very end means the input stopped in the middle
+        // of a command rather than being invalid.
+        Err(e) => {
+            let at_end = matches!(e.location, InputLocation::Pos(pos) if pos >= text.len());
+            at_end
+                && matches!(e.variant, ErrorVariant::ParsingError { positives, .. } if !positives.is_empty())
+        }
+    }
+}
+
+fn build_list(pair: Pair<Rule>, heredocs: &mut HereDocs) -> Result<List, String> {
+    let mut items: Vec<ListItem> = Vec::new();
+
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::and_or => items.push(ListItem::new(build_and_or(inner, heredocs)?, false)),
+            Rule::separator => {
+                let background = inner
+                    .into_inner()
+                    .next()
+                    .is_some_and(|separator| separator.as_rule() == Rule::background);
+                if background {
+                    if let Some(item) = items.last_mut() {
+                        item.background = true;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(List::new(items))
+}
+
+fn build_and_or(pair: Pair<Rule>, heredocs: &mut HereDocs) -> Result<AndOr, String> {
+    let mut inner = pair.into_inner();
+    let first = build_pipeline(inner.next().unwrap(), heredocs)?;
+    let mut rest = Vec::new();
+
+    while let Some(op) = inner.next() {
+        let operator = match op.into_inner().next().unwrap().as_rule() {
+            Rule::and_op => AndOrOperator::And,
+            _ => AndOrOperator::Or,
+        };
+        let pipeline = build_pipeline(inner.next().unwrap(), heredocs)?;
+        rest.push((operator, pipeline));
+    }
+
+    Ok(AndOr::new(first, rest))
+}
+
+fn build_pipeline(pair: Pair<Rule>, heredocs: &mut HereDocs) -> Result<Pipeline, String> {
+    let commands = pair
+        .into_inner()
+        .filter(|inner| inner.as_rule() == Rule::command)
+        .map(|command| build_command(command, heredocs))
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let command_pair = pairs.next().unwrap();
-    build_command(command_pair)
+    Ok(Pipeline::new(commands))
 }
 
-fn build_command(pair: Pair<Rule>) -> Result<Command, String> {
+fn build_command(pair: Pair<Rule>, heredocs: &mut HereDocs) -> Result<Command, String> {
+    let mut inner = pair.into_inner();
+    let first = inner.next().unwrap();
+    match first.as_rule() {
+        Rule::simple_command => return Ok(Command::Simple(build_simple_command(first, heredocs)?)),
+        Rule::function_definition => {
+            return Ok(Command::Function(build_function_definition(
+                first, heredocs,
+            )?));
+        }
+        _ => {}
+    }
+
+    let compound = build_compound_command(first, heredocs)?;
+    let mut redirections = Vec::new();
+    for redirection in inner {
+        redirections.extend(build_redirection(redirection, heredocs)?);
+    }
+    Ok(Command::Compound(compound, redirections))
+}
+
+fn build_simple_command(
+    pair: Pair<Rule>,
+    heredocs: &mut HereDocs,
+) -> Result<SimpleCommand, String> {
+    let mut assignments = Vec::new();
     let mut name = String::new();
     let mut args = Vec::new();
     let mut redirections = Vec::new();
-    let mut background = false;
 
     for inner in pair.into_inner() {
         match inner.as_rule() {
+            Rule::assignment => {
+                let mut parts = inner.into_inner();
+                let name = parts.next().unwrap().as_str().to_string();
+                let value = parts.next().unwrap().as_str().to_string();
+                assignments.push(Assignment::new(name, value));
+            }
             Rule::name => name = inner.as_str().to_string(),
             Rule::arg => args.push(inner.as_str().to_string()),
-            Rule::redirection => {
-                let mut inner_rules = inner.into_inner();
-                let op = inner_rules.next().unwrap();
-                let token = Token::from(op.as_str());
-                let redirectee = inner_rules.next().unwrap();
-                let redir = Redirection::try_from((token, redirectee.as_str().to_string()))?;
-                redirections.push(redir);
+            Rule::redirection => redirections.extend(build_redirection(inner, heredocs)?),
+            _ => {}
+        }
+    }
+
+    Ok(SimpleCommand::new(name, args, redirections).with_assignments(assignments))
+}
+
+fn build_function_definition(
+    pair: Pair<Rule>,
+    heredocs: &mut HereDocs,
+) -> Result<FunctionDefinition, String> {
+    let mut name = String::new();
+    let mut body = None;
+    let mut redirections = Vec::new();
+
+    for part in pair.into_inner() {
+        match part.as_rule() {
+            Rule::kw_function => {}
+            Rule::function_name => name = part.as_str().to_string(),
+            Rule::redirection => redirections.extend(build_redirection(part, heredocs)?),
+            _ => body = Some(build_compound_command(part, heredocs)?),
+        }
+    }
+
+    Ok(FunctionDefinition::new(name, body.unwrap(), redirections))
+}
+
+/// Builds the list of a `compound_list`, which is empty if there is none.
+fn build_compound_list(pair: Option<Pair<Rule>>, heredocs: &mut HereDocs) -> Result<List, String> {
+    match pair.and_then(|pair| pair.into_inner().next()) {
+        Some(list) => build_list(list, heredocs),
+        None => Ok(List::new(Vec::new())),
+    }
+}
+
+fn build_compound_command(
+    pair: Pair<Rule>,
+    heredocs: &mut HereDocs,
+) -> Result<CompoundCommand, String> {
+    let rule = pair.as_rule();
+    let mut lists = Vec::new();
+    let mut inner = pair.into_inner();
+
+    let command = match rule {
+        Rule::brace_group | Rule::subshell => {
+            let list = inner.find(|pair| pair.as_rule() == Rule::compound_list);
+            let list = build_compound_list(list, heredocs)?;
+            if rule == Rule::brace_group {
+                CompoundCommand::BraceGroup(list)
+            } else {
+                CompoundCommand::Subshell(list)
+            }
+        }
+        Rule::arith_command => {
+            CompoundCommand::Arithmetic(inner.next().unwrap().as_str().to_string())
+        }
+        Rule::if_clause => {
+            let mut else_body = None;
+            for part in inner {
+                match part.as_rule() {
+                    Rule::compound_list => lists.push(build_compound_list(Some(part), heredocs)?),
+                    Rule::elif_part => {
+                        for list in part.into_inner() {
+                            if list.as_rule() == Rule::compound_list {
+                                lists.push(build_compound_list(Some(list), heredocs)?);
+                            }
+                        }
+                    }
+                    Rule::else_part => {
+                        let list = part
+                            .into_inner()
+                            .find(|pair| pair.as_rule() == Rule::compound_list);
+                        else_body = Some(build_compound_list(list, heredocs)?);
+                    }
+                    _ => {}
+                }
+            }
+            let mut lists = lists.into_iter();
+            let mut branches = Vec::new();
+            while let (Some(condition), Some(body)) = (lists.next(), lists.next()) {
+                branches.push((condition, body));
+            }
+            CompoundCommand::If {
+                branches,
+                else_body,
+            }
+        }
+        Rule::while_clause | Rule::until_clause => {
+            for list in inner.filter(|pair| pair.as_rule() == Rule::compound_list) {
+                lists.push(build_compound_list(Some(list), heredocs)?);
+            }
+            let body = lists.pop().unwrap();
+            let condition = lists.pop().unwrap();
+            if rule == Rule::while_clause {
+                CompoundCommand::While { condition, body }
+            } else {
+                CompoundCommand::Until { condition, body }
+            }
+        }
+        Rule::for_clause => {
+            let mut name = String::new();
+            let mut words = None;
+            let mut body = None;
+            for part in inner {
+                match part.as_rule() {
+                    Rule::for_name => name = part.as_str().to_string(),
+                    Rule::for_words => {
+                        words = Some(part.into_inner().map(|w| w.as_str().to_string()).collect());
+                    }
+                    Rule::compound_list => body = Some(build_compound_list(Some(part), heredocs)?),
+                    _ => {}
+                }
+            }
+            CompoundCommand::For {
+                name,
+                words,
+                body: body.unwrap(),
+            }
+        }
+        Rule::case_clause => {
+            let mut word = String::new();
+            let mut items = Vec::new();
+            for part in inner {
+                match part.as_rule() {
+                    Rule::case_word => word = part.as_str().to_string(),
+                    Rule::case_item => items.push(build_case_item(part, heredocs)?),
+                    _ => {}
+                }
+            }
+            CompoundCommand::Case { word, items }
+        }
+        _ => unreachable!("unexpected compound command {rule:?}"),
+    };
+
+    Ok(command)
+}
+
+fn build_case_item(pair: Pair<Rule>, heredocs: &mut HereDocs) -> Result<CaseItem, String> {
+    let mut patterns = Vec::new();
+    let mut body = None;
+    let mut terminator = CaseTerminator::Break;
+
+    for part in pair.into_inner() {
+        match part.as_rule() {
+            Rule::case_pattern => patterns.push(part.as_str().to_string()),
+            Rule::compound_list => body = Some(part),
+            Rule::case_terminator => {
+                terminator = match part.as_str() {
+                    ";&" => CaseTerminator::FallThrough,
+                    ";;&" => CaseTerminator::Continue,
+                    _ => CaseTerminator::Break,
+                };
             }
-            Rule::background => background = true,
             _ => {}
         }
     }
 
-    Ok(Command::new(name, args, redirections, background))
+    let body = build_compound_list(body, heredocs)?;
+    Ok(CaseItem::new(patterns, body, terminator))
+}
+
+/// Builds the redirections of a redirection operator, `&>file` being
+/// equivalent to `>file 2>&1`.
+fn build_redirection(
+    pair: Pair<Rule>,
+    heredocs: &mut HereDocs,
+) -> Result<Vec<Redirection>, String> {
+    let mut fd = None;
+    let mut token = None;
+    let mut word = String::new();
+
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::io_number => {
+                let number = inner.as_str();
+                fd = Some(
+                    number
+                        .parse()
+                        .map_err(|_| format!("{number}: bad file descriptor"))?,
+                );
+            }
+            Rule::redir_op => token = Some(Token::from(inner.as_str())),
+            Rule::redirectee => word = inner.as_str().to_string(),
+            _ => {}
+        }
+    }
+
+    let token = token.unwrap();
+    let type_ = RedirectionType::try_from(token.clone())?;
+    let permissions = RedirectionPermission::try_from(token.clone())?;
+    let fd_given = fd.is_some();
+    let fd = fd.unwrap_or_else(|| type_.default_fd());
+
+    let redirectee = if matches!(token, Token::DoubleLangle | Token::DoubleLangleDash) {
+        let body = heredocs
+            .pop_front()
+            .ok_or_else(|| format!("{word}: here-document without a body"))?;
+        Redirectee::HereDoc {
+            body,
+            expand: !heredoc::is_quoted(&word),
+        }
+    } else if token == Token::TripleLangle {
+        Redirectee::HereString(word)
+    } else if matches!(token, Token::LangleAnd | Token::RangleAnd) {
+        // What the word stands for is only known once it is expanded.
+        Redirectee::Duplicate { word, fd_given }
+    } else {
+        Redirectee::FileName(word)
+    };
+
+    if matches!(token, Token::AndRangle | Token::AndDoubleRangle) {
+        return Ok(vec![
+            Redirection::new(1, redirectee, type_, permissions),
+            Redirection::new(
+                2,
+                Redirectee::FileDescriptor(1),
+                type_,
+                RedirectionPermission::Standard,
+            ),
+        ]);
+    }
+
+    Ok(vec![Redirection::new(fd, redirectee, type_, permissions)])
 }
 
 #[cfg(test)]
 mod tests {
-    use self::ast::{Redirectee, RedirectionPermission, RedirectionType};
-
     use super::*;
 
+    fn simple_list(commands: Vec<SimpleCommand>, background: bool) -> List {
+        let commands = commands.into_iter().map(Command::from).collect();
+        List::new(vec![ListItem::new(
+            AndOr::new(Pipeline::new(commands), Vec::new()),
+            background,
+        )])
+    }
+
     fn assert_empty(input: &str) {
         assert!(parse_command(input).is_err());
     }
@@ -74,12 +423,15 @@ mod tests {
         let command = command.unwrap();
         assert_eq!(
             command,
-            Command {
-                name: expected_name,
-                args: expected_args,
-                redirections: Vec::new(),
-                background: false,
-            }
+            simple_list(
+                vec![SimpleCommand {
+                    assignments: Vec::new(),
+                    name: expected_name,
+                    args: expected_args,
+                    redirections: Vec::new(),
+                }],
+                false
+            )
         );
     }
 
@@ -100,7 +452,7 @@ mod tests {
         );
     }
 
-    fn assert_command(input: &str, expected: Command) {
+    fn assert_command(input: &str, expected: List) {
         let command = parse_command(input);
         if let Err(s) = &command {
             println!("{s} for input \"{input}\"");
@@ -112,18 +464,22 @@ mod tests {
 
     fn test_simple_redirection(
         redirection_string: String,
+        fd: i32,
         type_: RedirectionType,
         permissions: RedirectionPermission,
     ) {
         assert_command(
             format!("a {redirection_string} b").as_str(),
-            Command::new(
-                "a".into(),
-                Vec::new(),
-                vec![Redirection::new(
-                    Redirectee::FileName("b".into()),
-                    type_,
-                    permissions,
+            simple_list(
+                vec![SimpleCommand::new(
+                    "a".into(),
+                    Vec::new(),
+                    vec![Redirection::new(
+                        fd,
+                        Redirectee::FileName("b".into()),
+                        type_,
+                        permissions,
+                    )],
                 )],
                 false,
             ),
@@ -134,50 +490,221 @@ mod tests {
     fn test_parse_command_simple_redirections() {
         test_simple_redirection(
             "<".into(),
-            RedirectionType::Stdin,
+            0,
+            RedirectionType::Input,
             RedirectionPermission::Standard,
         );
 
         test_simple_redirection(
             ">".into(),
-            RedirectionType::Stdout,
+            1,
+            RedirectionType::Output,
             RedirectionPermission::Standard,
         );
         test_simple_redirection(
             "2>".into(),
-            RedirectionType::Stderr,
+            2,
+            RedirectionType::Output,
             RedirectionPermission::Standard,
         );
 
         test_simple_redirection(
             ">|".into(),
-            RedirectionType::Stdout,
+            1,
+            RedirectionType::Output,
             RedirectionPermission::Truncate,
         );
         test_simple_redirection(
             "2>|".into(),
-            RedirectionType::Stderr,
+            2,
+            RedirectionType::Output,
             RedirectionPermission::Truncate,
         );
 
         test_simple_redirection(
             ">>".into(),
-            RedirectionType::Stdout,
+            1,
+            RedirectionType::Output,
             RedirectionPermission::Append,
         );
         test_simple_redirection(
             "2>>".into(),
-            RedirectionType::Stderr,
+            2,
+            RedirectionType::Output,
             RedirectionPermission::Append,
         );
     }
 
+    fn assert_redirections(input: &str, redirections: Vec<Redirection>) {
+        assert_command(
+            input,
+            simple_list(
+                vec![SimpleCommand::new("a".into(), Vec::new(), redirections)],
+                false,
+            ),
+        );
+    }
+
+    fn duplicate(word: &str, fd_given: bool) -> Redirectee {
+        Redirectee::Duplicate {
+            word: word.into(),
+            fd_given,
+        }
+    }
+
+    #[test]
+    fn test_parse_fd_redirections() {
+        use RedirectionPermission::{Append, Standard};
+        use RedirectionType::{Input, Output, ReadWrite};
+
+        assert_redirections(
+            "a 3< b",
+            vec![Redirection::new(
+                3,
+                Redirectee::FileName("b".into()),
+                Input,
+                Standard,
+            )],
+        );
+        assert_redirections(
+            "a 2>&1 >b",
+            vec![
+                Redirection::new(2, duplicate("1", true), Output, Standard),
+                Redirection::new(1, Redirectee::FileName("b".into()), Output, Standard),
+            ],
+        );
+        assert_redirections(
+            "a <&4 5>&-",
+            vec![
+                Redirection::new(0, duplicate("4", false), Input, Standard),
+                Redirection::new(5, duplicate("-", true), Output, Standard),
+            ],
+        );
+        assert_redirections(
+            "a >&$fd <&\"$in\"",
+            vec![
+                Redirection::new(1, duplicate("$fd", false), Output, Standard),
+                Redirection::new(0, duplicate("\"$in\"", false), Input, Standard),
+            ],
+        );
+        assert_redirections(
+            "a &>>b",
+            vec![
+                Redirection::new(1, Redirectee::FileName("b".into()), Output, Append),
+                Redirection::new(2, Redirectee::FileDescriptor(1), Output, Standard),
+            ],
+        );
+        assert_redirections(
+            "a 7<>b",
+            vec![Redirection::new(
+                7,
+                Redirectee::FileName("b".into()),
+                ReadWrite,
+                Standard,
+            )],
+        );
+    }
+
+    #[test]
+    fn test_parse_redirections_anywhere() {
+        use RedirectionPermission::Standard;
+        use RedirectionType::{Input, Output};
+
+        let file = |fd, name: &str, kind| {
+            Redirection::new(fd, Redirectee::FileName(name.into()), kind, Standard)
+        };
+        assert_command(
+            "echo >&2 error",
+            simple_list(
+                vec![SimpleCommand::new(
+                    "echo".into(),
+                    vec!["error".into()],
+                    vec![Redirection::new(1, duplicate("2", false), Output, Standard)],
+                )],
+                false,
+            ),
+        );
+        assert_command(
+            "echo >f hi <g there",
+            simple_list(
+                vec![SimpleCommand::new(
+                    "echo".into(),
+                    vec!["hi".into(), "there".into()],
+                    vec![file(1, "f", Output), file(0, "g", Input)],
+                )],
+                false,
+            ),
+        );
+        assert_redirections(">f a 2>g", vec![file(1, "f", Output), file(2, "g", Output)]);
+        assert_command(
+            "x=1 <f y=2 a",
+            simple_list(
+                vec![
+                    SimpleCommand::new("a".into(), Vec::new(), vec![file(0, "f", Input)])
+                        .with_assignments(vec![
+                            Assignment::new("x".into(), "1".into()),
+                            Assignment::new("y".into(), "2".into()),
+                        ]),
+                ],
+                false,
+            ),
+        );
+        assert_command(
+            ">f",
+            simple_list(
+                vec![SimpleCommand::new(
+                    String::new(),
+                    Vec::new(),
+                    vec![file(1, "f", Output)],
+                )],
+                false,
+            ),
+        );
+    }
+
+    #[test]
+    fn test_parse_heredocs() {
+        use RedirectionPermission::Standard;
+        use RedirectionType::Input;
+
+        assert_redirections(
+            "a <<EOF 3<<-'END' <<<\"$x y\"\nbody $x\nEOF\n\tquoted $x\n\tEND",
+            vec![
+                Redirection::new(
+                    0,
+                    Redirectee::HereDoc {
+                        body: "body $x\n".into(),
+                        expand: true,
+                    },
+                    Input,
+                    Standard,
+                ),
+                Redirection::new(
+                    3,
+                    Redirectee::HereDoc {
+                        body: "quoted $x\n".into(),
+                        expand: false,
+                    },
+                    Input,
+                    Standard,
+                ),
+                Redirection::new(
+                    0,
+                    Redirectee::HereString("\"$x y\"".into()),
+                    Input,
+                    Standard,
+                ),
+            ],
+        );
+        assert!(needs_more_input("a <<EOF\nbody"));
+        assert!(!needs_more_input("a <<EOF\nbody\nEOF"));
+    }
+
     #[test]
     fn test_invalid_redirections() {
         assert_empty(">");
         assert_empty("> >");
         assert_empty(" a >");
-        assert_empty("> b");
         assert_empty("a  > >");
         assert_empty("a  > b < ");
     }
@@ -186,18 +713,21 @@ mod tests {
     fn test_parse_multiple_redirections() {
         let redirections = vec![
             Redirection::new(
+                0,
                 Redirectee::FileName("b".into()),
-                RedirectionType::Stdin,
+                RedirectionType::Input,
                 RedirectionPermission::Standard,
             ),
             Redirection::new(
+                2,
                 Redirectee::FileName("c".into()),
-                RedirectionType::Stderr,
+                RedirectionType::Output,
                 RedirectionPermission::Truncate,
             ),
             Redirection::new(
+                1,
                 Redirectee::FileName("d".into()),
-                RedirectionType::Stdout,
+                RedirectionType::Output,
                 RedirectionPermission::Append,
             ),
         ];
@@ -205,7 +735,10 @@ mod tests {
         let input = "a < b 2>| c >> d";
         assert_command(
             input,
-            Command::new("a".into(), Vec::new(), redirections, false),
+            simple_list(
+                vec![SimpleCommand::new("a".into(), Vec::new(), redirections)],
+                false,
+            ),
         );
     }
 
@@ -215,12 +748,15 @@ mod tests {
         let command = command.unwrap();
         assert_eq!(
             command,
-            Command {
-                name: expected_name,
-                args: expected_args,
-                redirections: Vec::new(),
-                background: true,
-            }
+            simple_list(
+                vec![SimpleCommand {
+                    assignments: Vec::new(),
+                    name: expected_name,
+                    args: expected_args,
+                    redirections: Vec::new(),
+                }],
+                true
+            )
         );
     }
 
@@ -234,8 +770,6 @@ mod tests {
         assert_empty("   a &   &    ");
         assert_empty("   a  a &   &    ");
         assert_empty("a > & a &");
-        assert_empty("a a  & > a &");
-        assert_empty("a a  & > a");
     }
 
     #[test]
@@ -273,36 +807,552 @@ mod tests {
     #[test]
     fn test_background_with_redirections() {
         let redirections = vec![Redirection::new(
+            1,
             Redirectee::FileName("b".into()),
-            RedirectionType::Stdout,
+            RedirectionType::Output,
             RedirectionPermission::Standard,
         )];
         assert_command(
             "a > b &",
-            Command::new("a".into(), Vec::new(), redirections, true),
+            simple_list(
+                vec![SimpleCommand::new("a".into(), Vec::new(), redirections)],
+                true,
+            ),
         );
 
         let redirections = vec![
             Redirection::new(
+                0,
                 Redirectee::FileName("in".into()),
-                RedirectionType::Stdin,
+                RedirectionType::Input,
                 RedirectionPermission::Standard,
             ),
             Redirection::new(
+                2,
                 Redirectee::FileName("err".into()),
-                RedirectionType::Stderr,
+                RedirectionType::Output,
                 RedirectionPermission::Truncate,
             ),
             Redirection::new(
+                1,
                 Redirectee::FileName("out".into()),
-                RedirectionType::Stdout,
+                RedirectionType::Output,
                 RedirectionPermission::Append,
             ),
         ];
 
         assert_command(
             "a < in 2>| err >> out &",
-            Command::new("a".into(), Vec::new(), redirections, true),
+            simple_list(
+                vec![SimpleCommand::new("a".into(), Vec::new(), redirections)],
+                true,
+            ),
+        );
+    }
+
+    #[test]
+    fn test_parse_pipeline() {
+        assert_command(
+            "a | b c | d",
+            simple_list(
+                vec![
+                    SimpleCommand::new("a".into(), Vec::new(), Vec::new()),
+                    SimpleCommand::new("b".into(), vec!["c".into()], Vec::new()),
+                    SimpleCommand::new("d".into(), Vec::new(), Vec::new()),
+                ],
+                false,
+            ),
+        );
+        assert_command(
+            "a|b",
+            simple_list(
+                vec![
+                    SimpleCommand::new("a".into(), Vec::new(), Vec::new()),
+                    SimpleCommand::new("b".into(), Vec::new(), Vec::new()),
+                ],
+                false,
+            ),
+        );
+    }
+
+    #[test]
+    fn test_parse_pipeline_with_redirections() {
+        assert_command(
+            "a < in | b > out &",
+            simple_list(
+                vec![
+                    SimpleCommand::new(
+                        "a".into(),
+                        Vec::new(),
+                        vec![Redirection::new(
+                            0,
+                            Redirectee::FileName("in".into()),
+                            RedirectionType::Input,
+                            RedirectionPermission::Standard,
+                        )],
+                    ),
+                    SimpleCommand::new(
+                        "b".into(),
+                        Vec::new(),
+                        vec![Redirection::new(
+                            1,
+                            Redirectee::FileName("out".into()),
+                            RedirectionType::Output,
+                            RedirectionPermission::Standard,
+                        )],
+                    ),
+                ],
+                true,
+            ),
+        );
+    }
+
+    #[test]
+    fn test_pipeline_invalid() {
+        assert_empty("|");
+        assert_empty("| a");
+        assert_empty("a |");
+        assert_empty("a | | b");
+        assert_empty("a & | b");
+    }
+
+    #[test]
+    fn test_parse_and_or() {
+        let a = || {
+            Pipeline::new(vec![
+                SimpleCommand::new("a".into(), Vec::new(), Vec::new()).into()
+            ])
+        };
+        let b = || {
+            Pipeline::new(vec![
+                SimpleCommand::new("b".into(), Vec::new(), Vec::new()).into()
+            ])
+        };
+        let c = || {
+            Pipeline::new(vec![
+                SimpleCommand::new("c".into(), Vec::new(), Vec::new()).into()
+            ])
+        };
+
+        assert_command(
+            "a && b || c",
+            List::new(vec![ListItem::new(
+                AndOr::new(
+                    a(),
+                    vec![(AndOrOperator::And, b()), (AndOrOperator::Or, c())],
+                ),
+                false,
+            )]),
+        );
+        assert_command(
+            "a||b&",
+            List::new(vec![ListItem::new(
+                AndOr::new(a(), vec![(AndOrOperator::Or, b())]),
+                true,
+            )]),
+        );
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let item = |name: &str, background| {
+            ListItem::new(
+                AndOr::new(
+                    Pipeline::new(vec![SimpleCommand::new(
+                        name.into(),
+                        Vec::new(),
+                        Vec::new(),
+                    )
+                    .into()]),
+                    Vec::new(),
+                ),
+                background,
+            )
+        };
+
+        assert_command(
+            "a; b & c",
+            List::new(vec![item("a", false), item("b", true), item("c", false)]),
+        );
+        assert_command("a;", List::new(vec![item("a", false)]));
+        assert_command("a & b &", List::new(vec![item("a", true), item("b", true)]));
+    }
+
+    #[test]
+    fn test_list_invalid() {
+        assert_empty(";");
+        assert_empty("; a");
+        assert_empty("a;;");
+        assert_empty("a && ; b");
+        assert_empty("a ||");
+        assert_empty("&& a");
+        assert_empty("a ||| b");
+    }
+
+    #[test]
+    fn test_parse_quoted_words() {
+        assert_simple_comamnd(
+            "echo \"hello world\" 'a b' c\\ d",
+            "echo".to_string(),
+            vec![
+                "\"hello world\"".to_string(),
+                "'a b'".to_string(),
+                "c\\ d".to_string(),
+            ],
+        );
+        assert_simple_comamnd(
+            "'my cmd' a\"b; c\"d $'x\\'y'",
+            "'my cmd'".to_string(),
+            vec!["a\"b; c\"d".to_string(), "$'x\\'y'".to_string()],
         );
+        assert_simple_comamnd(
+            "echo \"a | b && c\"",
+            "echo".to_string(),
+            vec!["\"a | b && c\"".to_string()],
+        );
+    }
+
+    #[test]
+    fn test_parse_unterminated_quotes() {
+        assert_empty("echo \"abc");
+        assert_empty("echo 'abc");
+        assert_empty("echo $'abc");
+    }
+
+    #[test]
+    fn test_parse_command_substitution() {
+        assert_simple_comamnd(
+            "echo $(a | b; c) x$(d \")\")y \"$(e $(f))\"",
+            "echo".to_string(),
+            vec![
+                "$(a | b; c)".to_string(),
+                "x$(d \")\")y".to_string(),
+                "\"$(e $(f))\"".to_string(),
+            ],
+        );
+        assert_simple_comamnd(
+            "echo `a | b` `c \\` d`",
+            "echo".to_string(),
+            vec!["`a | b`".to_string(), "`c \\` d`".to_string()],
+        );
+        assert_empty("echo $(a");
+        assert_empty("echo `a");
+        assert_empty("echo $(a &&)");
+    }
+
+    #[test]
+    fn test_parse_process_substitution() {
+        assert_simple_comamnd(
+            "diff <(sort a) x>(tee b | c)",
+            "diff".to_string(),
+            vec!["<(sort a)".to_string(), "x>(tee b | c)".to_string()],
+        );
+        assert_redirections(
+            "a < <(b)",
+            vec![Redirection::new(
+                0,
+                Redirectee::FileName("<(b)".into()),
+                RedirectionType::Input,
+                RedirectionPermission::Standard,
+            )],
+        );
+        assert_empty("a <(b");
+    }
+
+    fn list_of(names: &[&str]) -> List {
+        List::new(
+            names
+                .iter()
+                .map(|name| {
+                    let command = SimpleCommand::new((*name).into(), Vec::new(), Vec::new());
+                    ListItem::new(
+                        AndOr::new(Pipeline::new(vec![command.into()]), Vec::new()),
+                        false,
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    fn compound_list(command: CompoundCommand, redirections: Vec<Redirection>) -> List {
+        List::new(vec![ListItem::new(
+            AndOr::new(
+                Pipeline::new(vec![Command::Compound(command, redirections)]),
+                Vec::new(),
+            ),
+            false,
+        )])
+    }
+
+    #[test]
+    fn test_parse_newlines_and_comments() {
+        assert_command("a\nb\n\nc\n", list_of(&["a", "b", "c"]));
+        assert_command("\n# comment\na # b c\nb", list_of(&["a", "b"]));
+        assert_command("a &&\n\nb", {
+            let b = SimpleCommand::new("b".into(), Vec::new(), Vec::new());
+            let a = SimpleCommand::new("a".into(), Vec::new(), Vec::new());
+            List::new(vec![ListItem::new(
+                AndOr::new(
+                    Pipeline::new(vec![a.into()]),
+                    vec![(AndOrOperator::And, Pipeline::new(vec![b.into()]))],
+                ),
+                false,
+            )])
+        });
+    }
+
+    #[test]
+    fn test_parse_groups() {
+        assert_command(
+            "{ a; b; }",
+            compound_list(
+                CompoundCommand::BraceGroup(list_of(&["a", "b"])),
+                Vec::new(),
+            ),
+        );
+        assert_command(
+            "(a\nb) >f",
+            compound_list(
+                CompoundCommand::Subshell(list_of(&["a", "b"])),
+                vec![Redirection::new(
+                    1,
+                    Redirectee::FileName("f".into()),
+                    RedirectionType::Output,
+                    RedirectionPermission::Standard,
+                )],
+            ),
+        );
+        assert_empty("{ a }");
+        assert_empty("{ a; }b");
+        assert_simple_comamnd("a {", "a".into(), vec!["{".into()]);
+    }
+
+    #[test]
+    fn test_parse_if() {
+        assert_command(
+            "if a; then b; elif c\nthen d; else e; fi",
+            compound_list(
+                CompoundCommand::If {
+                    branches: vec![
+                        (list_of(&["a"]), list_of(&["b"])),
+                        (list_of(&["c"]), list_of(&["d"])),
+                    ],
+                    else_body: Some(list_of(&["e"])),
+                },
+                Vec::new(),
+            ),
+        );
+        assert_empty("if a; then b");
+        assert_empty("if a; fi");
+        assert_simple_comamnd(
+            "echo if then",
+            "echo".into(),
+            vec!["if".into(), "then".into()],
+        );
+    }
+
+    #[test]
+    fn test_parse_loops() {
+        assert_command(
+            "while a; do b; done",
+            compound_list(
+                CompoundCommand::While {
+                    condition: list_of(&["a"]),
+                    body: list_of(&["b"]),
+                },
+                Vec::new(),
+            ),
+        );
+        assert_command(
+            "until a\ndo\nb\ndone",
+            compound_list(
+                CompoundCommand::Until {
+                    condition: list_of(&["a"]),
+                    body: list_of(&["b"]),
+                },
+                Vec::new(),
+            ),
+        );
+        assert_command(
+            "for i in x 'y z'; do a; done",
+            compound_list(
+                CompoundCommand::For {
+                    name: "i".into(),
+                    words: Some(vec!["x".into(), "'y z'".into()]),
+                    body: list_of(&["a"]),
+                },
+                Vec::new(),
+            ),
+        );
+        assert_command(
+            "for i\ndo a; done",
+            compound_list(
+                CompoundCommand::For {
+                    name: "i".into(),
+                    words: None,
+                    body: list_of(&["a"]),
+                },
+                Vec::new(),
+            ),
+        );
+        assert_command(
+            "for i in; do a; done",
+            compound_list(
+                CompoundCommand::For {
+                    name: "i".into(),
+                    words: Some(Vec::new()),
+                    body: list_of(&["a"]),
+                },
+                Vec::new(),
+            ),
+        );
+        assert_empty("while a; do b; od");
+        assert_empty("for 1 in a; do b; done");
+    }
+
+    #[test]
+    fn test_parse_case() {
+        assert_command(
+            "case $x in\n(a|b*) c;;\n'd') ;&\n*) e; f ;;&\nesac",
+            compound_list(
+                CompoundCommand::Case {
+                    word: "$x".into(),
+                    items: vec![
+                        CaseItem::new(
+                            vec!["a".into(), "b*".into()],
+                            list_of(&["c"]),
+                            CaseTerminator::Break,
+                        ),
+                        CaseItem::new(
+                            vec!["'d'".into()],
+                            List::new(Vec::new()),
+                            CaseTerminator::FallThrough,
+                        ),
+                        CaseItem::new(
+                            vec!["*".into()],
+                            list_of(&["e", "f"]),
+                            CaseTerminator::Continue,
+                        ),
+                    ],
+                },
+                Vec::new(),
+            ),
+        );
+        assert_command(
+            "case x in a) b; esac",
+            compound_list(
+                CompoundCommand::Case {
+                    word: "x".into(),
+                    items: vec![CaseItem::new(
+                        vec!["a".into()],
+                        list_of(&["b"]),
+                        CaseTerminator::Break,
+                    )],
+                },
+                Vec::new(),
+            ),
+        );
+        assert_empty("case x in a) b;; c");
+    }
+
+    #[test]
+    fn test_parse_arithmetic() {
+        assert_simple_comamnd(
+            "echo $((1 + (2 * 3))) \"$((x))\" $((a); (b))",
+            "echo".to_string(),
+            vec![
+                "$((1 + (2 * 3)))".to_string(),
+                "\"$((x))\"".to_string(),
+                "$((a); (b))".to_string(),
+            ],
+        );
+        assert_command(
+            "((i += (2)))",
+            compound_list(CompoundCommand::Arithmetic("i += (2)".into()), Vec::new()),
+        );
+        assert_command(
+            "((a) )",
+            compound_list(
+                CompoundCommand::Subshell(compound_list(
+                    CompoundCommand::Subshell(list_of(&["a"])),
+                    Vec::new(),
+                )),
+                Vec::new(),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_parse_assignments() {
+        let assignment = |name: &str, value: &str| Assignment::new(name.into(), value.into());
+        let command = |name: &str, args: &[&str], assignments| {
+            let args = args.iter().map(ToString::to_string).collect();
+            SimpleCommand::new(name.into(), args, Vec::new()).with_assignments(assignments)
+        };
+
+        assert_command(
+            "x=5",
+            simple_list(vec![command("", &[], vec![assignment("x", "5")])], false),
+        );
+        assert_command(
+            "a= b=\"$x y\"'z' env a=1",
+            simple_list(
+                vec![command(
+                    "env",
+                    &["a=1"],
+                    vec![assignment("a", ""), assignment("b", "\"$x y\"'z'")],
+                )],
+                false,
+            ),
+        );
+        assert_simple_comamnd("1x=5", "1x=5".into(), Vec::new());
+    }
+
+    #[test]
+    fn test_parse_function_definitions() {
+        let function = |name: &str, redirections| {
+            List::new(vec![ListItem::new(
+                AndOr::new(
+                    Pipeline::new(vec![Command::Function(FunctionDefinition::new(
+                        name.into(),
+                        CompoundCommand::BraceGroup(list_of(&["a"])),
+                        redirections,
+                    ))]),
+                    Vec::new(),
+                ),
+                false,
+            )])
+        };
+
+        assert_command("f() { a; }", function("f", Vec::new()));
+        assert_command("my-func ()\n{\na\n}", function("my-func", Vec::new()));
+        assert_command("function f { a; }", function("f", Vec::new()));
+        assert_command(
+            "function f() { a; } 2>&1",
+            function(
+                "f",
+                vec![Redirection::new(
+                    2,
+                    duplicate("1", true),
+                    RedirectionType::Output,
+                    RedirectionPermission::Standard,
+                )],
+            ),
+        );
+        assert_empty("f() a");
+        assert_empty("if() { a; }");
+    }
+
+    #[test]
+    fn test_needs_more_input() {
+        assert!(!needs_more_input("a"));
+        assert!(!needs_more_input(""));
+        assert!(!needs_more_input("# if"));
+        assert!(!needs_more_input("a; }"));
+        assert!(needs_more_input("if a; then"));
+        assert!(needs_more_input("while a\ndo b"));
+        assert!(needs_more_input("a |"));
+        assert!(!needs_more_input("a; fi"));
+        assert!(!needs_more_input("if a; then b; fi"));
     }
 }
@@ -8,9 +8,14 @@ pub enum Token {
     Rangle,
     RangleF,
     DoubleRangle,
-    Rangle2,
-    Rangle2F,
-    DoubleRangle2,
+    DoubleLangle,
+    DoubleLangleDash,
+    TripleLangle,
+    LangleRangle,
+    LangleAnd,
+    RangleAnd,
+    AndRangle,
+    AndDoubleRangle,
 
     And,
 }
@@ -23,9 +28,14 @@ impl Display for Token {
             Self::Rangle => write!(f, ">"),
             Self::RangleF => write!(f, ">|"),
             Self::DoubleRangle => write!(f, ">>"),
-            Self::Rangle2 => write!(f, "2>"),
-            Self::Rangle2F => write!(f, "2>|"),
-            Self::DoubleRangle2 => write!(f, "2>>"),
+            Self::DoubleLangle => write!(f, "<<"),
+            Self::DoubleLangleDash => write!(f, "<<-"),
+            Self::TripleLangle => write!(f, "<<<"),
+            Self::LangleRangle => write!(f, "<>"),
+            Self::LangleAnd => write!(f, "<&"),
+            Self::RangleAnd => write!(f, ">&"),
+            Self::AndRangle => write!(f, "&>"),
+            Self::AndDoubleRangle => write!(f, "&>>"),
             Self::And => write!(f, "&"),
         }
     }
@@ -38,9 +48,14 @@ impl From<&str> for Token {
             ">" => Self::Rangle,
             ">|" => Self::RangleF,
             ">>" => Self::DoubleRangle,
-            "2>" => Self::Rangle2,
-            "2>|" => Self::Rangle2F,
-            "2>>" => Self::DoubleRangle2,
+            "<<" => Self::DoubleLangle,
+            "<<-" => Self::DoubleLangleDash,
+            "<<<" => Self::TripleLangle,
+            "<>" => Self::LangleRangle,
+            "<&" => Self::LangleAnd,
+            ">&" => Self::RangleAnd,
+            "&>" => Self::AndRangle,
+            "&>>" => Self::AndDoubleRangle,
             "&" => Self::And,
             _ => Self::String(s.to_string()),
         }
@@ -1,6 +1,14 @@
 use std::fmt::Display;
 
-use super::{ExitStatus, Process, Status};
+use nix::{
+    sys::{
+        signal::{killpg, Signal},
+        termios::Termios,
+    },
+    unistd::Pid,
+};
+
+use super::{terminal, ExitStatus, Process, Status};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Pgid(pub i32);
@@ -8,11 +16,19 @@ pub struct Pgid(pub i32);
 pub struct Job {
     pub id: usize,
     pub pgid: Pgid,
-    leader: usize,
     pub background: bool,
     pub last_status: Status,
     pub name: String,
     pub processes: Vec<Box<dyn Process>>,
+    /// Processes started for the job, like process substitutions, which
+    /// are reaped with it but do not make up its status.
+    pub auxiliary: Vec<Box<dyn Process>>,
+    /// The terminal modes the job had when it was stopped, given back to it
+    /// when it is resumed in the foreground.
+    pub modes: Option<Termios>,
+    /// Whether changes of status are printed, which they are under job
+    /// control.
+    pub notify: bool,
 }
 
 impl Display for Job {
@@ -35,20 +51,26 @@ impl Job {
     ) -> Self {
         Self {
             id: 0,
-            // Find a better way of dealing with this.
-            // Maybe leader should be on the processes itself ?
-            // Or an argument of this function, which is already quite big...
-            leader: 0,
             pgid,
             processes,
+            auxiliary: Vec::new(),
+            modes: None,
+            notify: false,
             last_status,
             background,
             name,
         }
     }
 
+    pub fn add_auxiliary(&mut self, processes: Vec<Box<dyn Process>>) {
+        self.auxiliary.extend(processes);
+    }
+
     fn update_status(&mut self) {
-        if self.processes.iter().any(|p| p.status() == Status::Running) {
+        let finished = self.processes.iter().all(|p| p.status().is_finished());
+        if self.processes.iter().any(|p| p.status() == Status::Running)
+            || (finished && self.auxiliary.iter().any(|p| p.status() == Status::Running))
+        {
             self.last_status = Status::Running;
         } else if self.processes.iter().any(|p| p.status() == Status::Stopped) {
             self.last_status = Status::Stopped;
@@ -70,11 +92,21 @@ impl Job {
             process.wait(blocking)?;
         }
 
+        // Waiting on the auxiliary processes of a stopped job would hang.
+        let finished = self.processes.iter().all(|p| p.status().is_finished());
+        for process in &mut self.auxiliary {
+            if process.status().is_finished() {
+                continue;
+            }
+            process.wait(blocking && finished)?;
+        }
+
         let last_status = self.last_status;
 
         self.update_status();
 
-        if last_status != self.last_status {
+        // Jobs get an id, and are printed, once they are in the job table.
+        if last_status != self.last_status && self.id != 0 && self.notify {
             // We should not print an update on a foreground job that is finished
             if self.background || !self.last_status.is_finished() {
                 println!("{self}");
@@ -84,7 +116,47 @@ impl Job {
         Ok(())
     }
 
+    /// Waits for the job to be done or stopped in the foreground. Under
+    /// `job_control`, it has the terminal in the meantime.
+    pub fn wait_in_foreground(&mut self, job_control: bool) -> anyhow::Result<()> {
+        // Jobs run by the shell itself have no process group.
+        if !job_control || self.pgid.0 == 0 {
+            return self.update(true);
+        }
+        let _ = terminal::give_to(self.pgid);
+        let result = self.update(true);
+        if self.last_status == Status::Stopped {
+            self.modes = terminal::modes().ok();
+        }
+        terminal::take_back()?;
+        result
+    }
+
+    /// Continues a stopped job in the background, or in the foreground where
+    /// it is waited for with the terminal modes it was stopped with.
+    pub fn resume(&mut self, background: bool, job_control: bool) -> anyhow::Result<()> {
+        self.background = background;
+        if !background && job_control {
+            if let Some(modes) = self.modes.take() {
+                let _ = terminal::set_modes(&modes);
+            }
+        }
+        killpg(Pid::from_raw(self.pgid.0), Signal::SIGCONT)?;
+        for process in &mut self.processes {
+            process.continued();
+        }
+        self.update_status();
+
+        if background {
+            Ok(())
+        } else {
+            self.wait_in_foreground(job_control)
+        }
+    }
+
+    /// The exit status of a job is the one of its last process, as it is
+    /// for pipelines.
     pub fn exit_status(&self) -> Option<ExitStatus> {
-        self.processes[self.leader].exit_status()
+        self.processes.last()?.exit_status()
     }
 }
@@ -1,38 +1,53 @@
+use std::collections::VecDeque;
+
 use anyhow::anyhow;
 
-use super::job::Job;
+use super::{job::Job, Status};
+
+/// How many statuses of finished processes are kept for `wait`.
+const FINISHED_LIMIT: usize = 1024;
 
 #[derive(Default)]
 pub struct JobTable {
     table: Vec<Option<Job>>,
     size: usize,
+    // The ids of the jobs, from the least to the most recently started,
+    // stopped or resumed.
+    order: Vec<usize>,
+    // The pids and exit codes of the processes of the jobs removed once
+    // done, which `wait` can still report.
+    finished: VecDeque<(i32, i32)>,
 }
 
 impl JobTable {
-    pub fn add_job(&mut self, mut job: Job) {
-        if self.size >= self.table.len() {
-            job.id = self.size + 1;
+    /// Adds a job and returns the id it was given, the lowest one free.
+    pub fn add_job(&mut self, mut job: Job) -> usize {
+        let index = self
+            .table
+            .iter()
+            .position(Option::is_none)
+            .unwrap_or(self.table.len());
+        job.id = index + 1;
+        if index == self.table.len() {
             self.table.push(Some(job));
         } else {
-            for i in 0..self.table.len() {
-                if self.table[i].is_none() {
-                    job.id = i + 1;
-                    self.table[i] = Some(job);
-                    break;
-                }
-            }
+            self.table[index] = Some(job);
         }
         self.size += 1;
+        self.order.push(index + 1);
+        index + 1
     }
 
     pub fn remove_job(&mut self, id: usize) -> Result<(), anyhow::Error> {
-        if id > self.table.len() && self.size != 0 {
-            Err(anyhow!("Job index out of bounds"))
-        } else {
-            self.table[id - 1] = None;
-            self.size -= 1;
-            Ok(())
-        }
+        let slot = id
+            .checked_sub(1)
+            .and_then(|index| self.table.get_mut(index))
+            .filter(|slot| slot.is_some())
+            .ok_or_else(|| anyhow!("Job index out of bounds"))?;
+        *slot = None;
+        self.size -= 1;
+        self.order.retain(|&job| job != id);
+        Ok(())
     }
 
     pub const fn size(&self) -> usize {
@@ -41,27 +56,262 @@ impl JobTable {
 
     pub fn update(&mut self) -> Result<(), anyhow::Error> {
         let mut to_remove = Vec::new();
+        let mut stopped = Vec::new();
         for job in self.table.iter_mut().flatten() {
+            let last_status = job.last_status;
             job.update(false)?;
             if job.last_status.is_finished() {
                 to_remove.push(job.id);
+            } else if job.last_status == Status::Stopped && last_status != Status::Stopped {
+                stopped.push(job.id);
             }
         }
 
         for id in to_remove {
+            self.keep_statuses(id);
             self.remove_job(id)?;
         }
+        for id in stopped {
+            self.make_current(id);
+        }
 
         Ok(())
     }
 
+    fn keep_statuses(&mut self, id: usize) {
+        let Some(job) = self.get_job(id) else {
+            return;
+        };
+        let statuses: Vec<(i32, i32)> = job
+            .processes
+            .iter()
+            .filter_map(|process| {
+                let status = process.exit_status()?;
+                Some((process.pid().0, status.to_exit_code()))
+            })
+            .collect();
+        self.finished.extend(statuses);
+        while self.finished.len() > FINISHED_LIMIT {
+            self.finished.pop_front();
+        }
+    }
+
+    /// Takes the exit code of a process of a job removed once done, which
+    /// is only reported once.
+    pub fn take_finished(&mut self, pid: i32) -> Option<i32> {
+        let index = self.finished.iter().position(|&(p, _)| p == pid)?;
+        self.finished.remove(index).map(|(_, code)| code)
+    }
+
     pub fn print_jobs(&self) {
         for job in self.table.iter().flatten() {
-            println!("{job}");
+            self.print_job(job.id);
         }
     }
 
+    /// Prints a job as `jobs` does, marking the current job with `+` and the
+    /// previous one with `-`.
+    pub fn print_job(&self, id: usize) {
+        let Some(job) = self.get_job(id) else {
+            return;
+        };
+        let marker = if self.current() == Some(id) {
+            '+'
+        } else if self.previous() == Some(id) {
+            '-'
+        } else {
+            ' '
+        };
+        println!(
+            "[{}]{marker}\t{}\t{}\t{}",
+            job.id, job.pgid.0, job.last_status, job.name
+        );
+    }
+
     pub fn get_job(&self, id: usize) -> Option<&Job> {
-        self.table.get(id - 1)?.as_ref()
+        self.table.get(id.checked_sub(1)?)?.as_ref()
+    }
+
+    pub fn get_job_mut(&mut self, id: usize) -> Option<&mut Job> {
+        self.table.get_mut(id.checked_sub(1)?)?.as_mut()
+    }
+
+    /// The ids of all the jobs.
+    pub fn ids(&self) -> Vec<usize> {
+        self.table.iter().flatten().map(|job| job.id).collect()
+    }
+
+    /// The id of the job a process belongs to.
+    pub fn find_pid(&self, pid: i32) -> Option<usize> {
+        self.table
+            .iter()
+            .flatten()
+            .find(|job| job.processes.iter().any(|process| process.pid().0 == pid))
+            .map(|job| job.id)
+    }
+
+    /// Makes a job the current one, as when it is stopped or resumed.
+    pub fn make_current(&mut self, id: usize) {
+        self.order.retain(|&job| job != id);
+        self.order.push(id);
+    }
+
+    /// The ids of the jobs from the most recent one, stopped jobs first.
+    fn by_recency(&self) -> Vec<usize> {
+        let mut ids: Vec<usize> = self.order.iter().rev().copied().collect();
+        ids.sort_by_key(|&id| {
+            self.get_job(id)
+                .is_none_or(|job| job.last_status != Status::Stopped)
+        });
+        ids
+    }
+
+    /// The current job, `%+`, the default of the builtins taking a job.
+    pub fn current(&self) -> Option<usize> {
+        self.by_recency().first().copied()
+    }
+
+    /// The previous job, `%-`.
+    pub fn previous(&self) -> Option<usize> {
+        self.by_recency().get(1).copied()
+    }
+
+    /// Finds the id of the job named by a job specification: `%N` for the
+    /// job N, `%+`, `%%` or `%` for the current job, `%-` for the previous
+    /// one, `%name` for the job whose command starts with `name` and
+    /// `%?text` for the one containing `text`. The `%` can be left out.
+    ///
+    /// With a single job, `%-` is the current job as well.
+    pub fn resolve(&self, spec: &str) -> anyhow::Result<usize> {
+        let name = spec.strip_prefix('%').unwrap_or(spec);
+        let id = match name {
+            "" | "%" | "+" => {
+                return self
+                    .current()
+                    .ok_or_else(|| anyhow!("current: no such job"))
+            }
+            "-" => self.previous().or_else(|| self.current()),
+            _ if name.bytes().all(|b| b.is_ascii_digit()) => {
+                name.parse().ok().filter(|&id| self.get_job(id).is_some())
+            }
+            _ => {
+                let matches: Vec<usize> = self
+                    .table
+                    .iter()
+                    .flatten()
+                    .filter(|job| match name.strip_prefix('?') {
+                        Some(text) => job.name.contains(text),
+                        None => job.name.starts_with(name),
+                    })
+                    .map(|job| job.id)
+                    .collect();
+                if matches.len() > 1 {
+                    return Err(anyhow!("{spec}: ambiguous job spec"));
+                }
+                matches.first().copied()
+            }
+        };
+        id.ok_or_else(|| anyhow!("{spec}: no such job"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::proc::{job::Pgid, InternalProcess};
+
+    use super::*;
+
+    fn job(name: &str, status: Status) -> Job {
+        let process = InternalProcess::new(name.to_string(), 0);
+        Job::new(Pgid(0), vec![Box::new(process)], status, true, name.into())
+    }
+
+    fn table(jobs: &[(&str, Status)]) -> JobTable {
+        let mut table = JobTable::default();
+        for (name, status) in jobs {
+            table.add_job(job(name, *status));
+        }
+        table
+    }
+
+    #[test]
+    fn test_current_and_previous() {
+        let mut table = table(&[("sleep 1 ", Status::Running), ("vim a ", Status::Running)]);
+        assert_eq!(table.current(), Some(2));
+        assert_eq!(table.previous(), Some(1));
+
+        table.make_current(1);
+        assert_eq!(table.current(), Some(1));
+        assert_eq!(table.previous(), Some(2));
+
+        table.add_job(job("less b ", Status::Stopped));
+        table.add_job(job("sleep 2 ", Status::Running));
+        assert_eq!(table.current(), Some(3));
+        assert_eq!(table.previous(), Some(4));
+
+        table.remove_job(3).unwrap();
+        assert_eq!(table.current(), Some(4));
+        assert_eq!(table.previous(), Some(1));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let table = table(&[
+            ("sleep 10 ", Status::Running),
+            ("vim notes ", Status::Running),
+            ("sleep 20 ", Status::Running),
+        ]);
+        assert_eq!(table.resolve("%1").unwrap(), 1);
+        assert_eq!(table.resolve("2").unwrap(), 2);
+        for spec in ["%", "%%", "%+"] {
+            assert_eq!(table.resolve(spec).unwrap(), 3);
+        }
+        assert_eq!(table.resolve("%-").unwrap(), 2);
+        assert_eq!(table.resolve("%vim").unwrap(), 2);
+        assert_eq!(table.resolve("%?20").unwrap(), 3);
+        assert_eq!(table.resolve("%?note").unwrap(), 2);
+
+        assert_eq!(
+            table.resolve("%sleep").unwrap_err().to_string(),
+            "%sleep: ambiguous job spec"
+        );
+        assert_eq!(
+            table.resolve("%?e").unwrap_err().to_string(),
+            "%?e: ambiguous job spec"
+        );
+        assert_eq!(
+            table.resolve("%4").unwrap_err().to_string(),
+            "%4: no such job"
+        );
+        assert_eq!(
+            table.resolve("%ls").unwrap_err().to_string(),
+            "%ls: no such job"
+        );
+        assert_eq!(
+            JobTable::default().resolve("%").unwrap_err().to_string(),
+            "current: no such job"
+        );
+        assert_eq!(
+            JobTable::default().resolve("%-").unwrap_err().to_string(),
+            "%-: no such job"
+        );
+    }
+
+    #[test]
+    fn test_resolve_single_job() {
+        let table = table(&[("sleep 10 ", Status::Running)]);
+        assert_eq!(table.resolve("%-").unwrap(), 1);
+        assert_eq!(table.previous(), None);
+    }
+
+    #[test]
+    fn test_remove_job() {
+        let mut table = table(&[("sleep 1 ", Status::Running), ("sleep 2 ", Status::Running)]);
+        assert!(table.remove_job(0).is_err());
+        assert!(table.remove_job(3).is_err());
+        table.remove_job(1).unwrap();
+        assert!(table.remove_job(1).is_err());
+        assert_eq!(table.size(), 1);
+        assert!(JobTable::default().remove_job(1).is_err());
     }
 }
@@ -6,6 +6,7 @@ use nix::{
 
 pub mod job;
 pub mod job_table;
+pub mod terminal;
 
 #[derive(EnumStringify, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Status {
@@ -16,7 +17,7 @@ pub enum Status {
 }
 
 impl Status {
-    const fn is_finished(&self) -> bool {
+    pub const fn is_finished(&self) -> bool {
         match self {
             Self::Running | Self::Stopped => false,
             Self::Done | Self::Killed => true,
@@ -59,6 +60,15 @@ impl ExitStatus {
         }
     }
 
+    /// The exit code reported by the shell, which is `128 + signal` for
+    /// processes that were killed or stopped by a signal.
+    pub const fn to_exit_code(&self) -> i32 {
+        match &self.exit_status {
+            ExitStatusEnum::Done(code) => *code,
+            ExitStatusEnum::Killed(sig) | ExitStatusEnum::Stopped(sig) => 128 + *sig,
+        }
+    }
+
     pub const fn stopped_signal(&self) -> Option<i32> {
         match &self.exit_status {
             ExitStatusEnum::Done(_) => None,
@@ -128,6 +138,8 @@ pub trait Process {
     fn status(&self) -> Status;
     fn exit_status(&self) -> Option<ExitStatus>;
     fn wait(&mut self, blocking: bool) -> Result<Status, anyhow::Error>;
+    /// Marks a stopped process as running again, once it was sent `SIGCONT`.
+    fn continued(&mut self);
 }
 
 #[derive(Debug)]
@@ -167,6 +179,12 @@ impl Process for ExternalProcesss {
         self.status.update(wait_res);
         Ok(self.status.status)
     }
+
+    fn continued(&mut self) {
+        if self.status.status == Status::Stopped {
+            self.status = ProcessStatus::default();
+        }
+    }
 }
 
 impl ExternalProcesss {
@@ -202,6 +220,8 @@ impl Process for InternalProcess {
     fn wait(&mut self, _blocking: bool) -> Result<Status, anyhow::Error> {
         Ok(self.status.status)
     }
+
+    fn continued(&mut self) {}
 }
 
 impl InternalProcess {
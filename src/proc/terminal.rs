@@ -0,0 +1,157 @@
+use std::{
+    cell::RefCell,
+    io::IsTerminal,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use nix::{
+    libc::{c_int, STDIN_FILENO},
+    sys::{
+        signal::{killpg, sigaction, signal, SaFlags, SigAction, SigHandler, SigSet, Signal},
+        termios::{tcgetattr, tcsetattr, SetArg, Termios},
+    },
+    unistd::{getpgrp, getpid, setpgid, tcgetpgrp, tcsetpgrp, Pid},
+};
+
+use super::job::Pgid;
+
+thread_local! {
+    /// The terminal modes of the shell, put back when it takes the terminal
+    /// back from a job.
+    static SHELL_MODES: RefCell<Option<Termios>> = const { RefCell::new(None) };
+}
+
+/// Set when the shell gets SIGINT, until the command line it interrupted is
+/// over.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// The signals meant for the job in the foreground, which a shell with job
+/// control ignores, except for SIGINT which stops the commands it runs
+/// itself.
+const JOB_CONTROL_SIGNALS: [Signal; 4] = [
+    Signal::SIGINT,
+    Signal::SIGTSTP,
+    Signal::SIGTTIN,
+    Signal::SIGTTOU,
+];
+
+/// Whether the shell can control jobs, which takes a terminal.
+pub fn is_available() -> bool {
+    std::io::stdin().is_terminal()
+}
+
+/// Sets up job control: waits to be in the foreground if the shell was
+/// started in the background, then puts the shell in its own process group
+/// and gives it the terminal.
+pub fn init() -> anyhow::Result<()> {
+    loop {
+        let pgid = getpgrp();
+        if tcgetpgrp(STDIN_FILENO)? == pgid {
+            break;
+        }
+        killpg(pgid, Signal::SIGTTIN)?;
+    }
+
+    catch_interrupt()?;
+    for sig in JOB_CONTROL_SIGNALS {
+        if sig != Signal::SIGINT {
+            unsafe { signal(sig, SigHandler::SigIgn) }?;
+        }
+    }
+
+    let pid = getpid();
+    if getpgrp() != pid {
+        setpgid(pid, pid)?;
+    }
+    tcsetpgrp(STDIN_FILENO, pid)?;
+    let modes = modes()?;
+    SHELL_MODES.with(|shell_modes| *shell_modes.borrow_mut() = Some(modes));
+    Ok(())
+}
+
+extern "C" fn on_interrupt(_: c_int) {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+}
+
+/// Catches SIGINT, without SA_RESTART so that it interrupts `wait` as well.
+pub(crate) fn catch_interrupt() -> nix::Result<()> {
+    let action = SigAction::new(
+        SigHandler::Handler(on_interrupt),
+        SaFlags::empty(),
+        SigSet::empty(),
+    );
+    unsafe { sigaction(Signal::SIGINT, &action) }.map(drop)
+}
+
+/// Whether the shell got SIGINT while running the current command line, in
+/// which case it runs no more commands.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// Acts as if SIGINT was received, for a job in the foreground killed by it.
+pub fn interrupt() {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+}
+
+/// Clears the interruption once the command line is over, and returns
+/// whether there was one.
+pub fn take_interrupt() -> bool {
+    INTERRUPTED.swap(false, Ordering::Relaxed)
+}
+
+/// Restores the signals ignored or caught by the shell in a child, before it
+/// runs a command.
+pub fn reset_signals() -> nix::Result<()> {
+    for sig in JOB_CONTROL_SIGNALS {
+        unsafe { signal(sig, SigHandler::SigDfl) }?;
+    }
+    Ok(())
+}
+
+/// Puts the process group of a job in the foreground of the terminal.
+pub fn give_to(pgid: Pgid) -> nix::Result<()> {
+    tcsetpgrp(STDIN_FILENO, Pid::from_raw(pgid.0))
+}
+
+/// Puts the shell back in the foreground once its job is done or stopped,
+/// with the terminal modes it had.
+pub fn take_back() -> nix::Result<()> {
+    tcsetpgrp(STDIN_FILENO, getpgrp())?;
+    SHELL_MODES.with(|shell_modes| match &*shell_modes.borrow() {
+        Some(modes) => set_modes(modes),
+        None => Ok(()),
+    })
+}
+
+/// The current modes of the terminal.
+pub fn modes() -> nix::Result<Termios> {
+    tcgetattr(std::io::stdin())
+}
+
+pub fn set_modes(modes: &Termios) -> nix::Result<()> {
+    tcsetattr(std::io::stdin(), SetArg::TCSADRAIN, modes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        shell::Shell,
+        test_util::{catching_interrupts, run},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_interrupt_stops_loops() {
+        catching_interrupts(|shell| {
+            run(
+                shell,
+                "n=0; while ((n < 10)); do n=$((n + 1)); kill -2 $$; done; echo not reached",
+            ) == 0
+                && shell.get_var("n").as_deref() == Some("1")
+                && take_interrupt()
+                && !interrupted()
+        });
+    }
+}
@@ -7,7 +7,13 @@ pub fn get_prompt(shell: &dyn Shell) -> anyhow::Result<String> {
     let cwd = cwd
         .to_str()
         .ok_or(anyhow::anyhow!("invalid current directory"))?
-        .replace(std::env::var("HOME")?.as_str(), "~");
+        .replace(
+            shell
+                .get_var("HOME")
+                .ok_or(anyhow::anyhow!("HOME not set"))?
+                .as_str(),
+            "~",
+        );
 
     let new_cwd = if cwd.len() > 45 {
         format!("...{}", &cwd[cwd.len() - 45..])
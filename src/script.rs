@@ -0,0 +1,144 @@
+use std::{
+    fs::File,
+    io::{BufReader, ErrorKind, Read},
+    path::Path,
+};
+
+use anyhow::anyhow;
+use nix::errno::Errno;
+
+use crate::{
+    error::{self, report},
+    exec::execute_list,
+    parser::{is_blank, needs_more_input, parse_command_with_aliases},
+    proc::terminal,
+    shell::Shell,
+};
+
+/// Reads a line a byte at a time, so that the commands run can read what
+/// follows it from the same input. Returns `None` at the end of the input.
+fn read_line(reader: &mut dyn Read) -> std::io::Result<Option<String>> {
+    let mut line = Vec::new();
+    let mut byte = [0];
+    loop {
+        match reader.read(&mut byte) {
+            Ok(0) if line.is_empty() => return Ok(None),
+            Ok(0) => break,
+            Ok(_) if byte[0] == b'\n' => break,
+            Ok(_) => line.push(byte[0]),
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(Some(String::from_utf8_lossy(&line).into_owned()))
+}
+
+/// Runs the commands read from `reader`, each one as soon as it is
+/// complete, until the end of the input, `exit` or `return`, and returns
+/// the exit code of the last one.
+///
+/// Diagnostics are reported with `name` and the line the command starts at.
+/// Syntax errors stop the script with the exit code 2.
+pub fn run_script(shell: &mut dyn Shell, reader: &mut dyn Read, name: &str) -> i32 {
+    // A script sourced from another one reports at its own lines until it is
+    // done.
+    let outer = error::set_location(None);
+    let code = run_lines(shell, reader, name);
+    error::set_location(outer);
+    code
+}
+
+fn run_lines(shell: &mut dyn Shell, reader: &mut dyn Read, name: &str) -> i32 {
+    let mut line_number = 0;
+
+    while !shell.should_exit() && shell.control_flow().is_none() && !terminal::interrupted() {
+        let start = line_number + 1;
+        let mut command = String::new();
+        let mut end = false;
+        loop {
+            match read_line(reader) {
+                Ok(Some(line)) => {
+                    line_number += 1;
+                    command.push_str(&line);
+                    command.push('\n');
+                    if !needs_more_input(&command) {
+                        break;
+                    }
+                }
+                Ok(None) => {
+                    end = true;
+                    break;
+                }
+                Err(e) => {
+                    error::set_location(None);
+                    report(format_args!("{name}: {e}"));
+                    return 1;
+                }
+            }
+        }
+
+        if !is_blank(&command) {
+            error::set_location(Some((name.to_string(), start)));
+            match parse_command_with_aliases(&command, shell.aliases()) {
+                Ok(list) => {
+                    execute_list(shell, &list);
+                }
+                Err(e) => {
+                    report(format_args!("syntax error\n{e}"));
+                    return 2;
+                }
+            }
+        }
+        if end {
+            break;
+        }
+    }
+
+    shell.last_exit_code()
+}
+
+/// Runs the commands of a file, reporting errors with its path.
+pub fn run_file(shell: &mut dyn Shell, path: &Path) -> anyhow::Result<i32> {
+    let file = File::open(path).map_err(|e| {
+        let errno = Errno::from_i32(e.raw_os_error().unwrap_or(0));
+        anyhow!("{}: {}", path.display(), errno.desc())
+    })?;
+    Ok(run_script(
+        shell,
+        &mut BufReader::new(file),
+        &path.display().to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shell::DefaultShell;
+
+    use super::*;
+
+    #[test]
+    fn test_run_file_reports_lines() {
+        let dir = std::env::temp_dir().join(format!("rjsh-script-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (script, errors) = (dir.join("rc"), dir.join("errors"));
+        let errors = errors.display();
+        let text =
+            format!("true\n{{ echo x >/rjsh/missing; }} 2>{errors}\n\nrjsh-missing 2>>{errors}\n");
+        std::fs::write(&script, text).unwrap();
+
+        let mut shell = DefaultShell::default();
+        run_file(&mut shell, &script).unwrap();
+        let name = script.display();
+        assert_eq!(
+            std::fs::read_to_string(errors.to_string()).unwrap(),
+            format!(
+                "rjsh: {name}:2: /rjsh/missing: No such file or directory\n\
+                 rjsh: {name}:4: rjsh-missing: command not found\n"
+            )
+        );
+        // Diagnostics after the file are not reported at its lines.
+        assert!(error::set_location(None).is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
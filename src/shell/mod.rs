@@ -1,10 +1,33 @@
-use crate::proc::{job::Job, job_table::JobTable};
+use std::{collections::HashMap, os::fd::RawFd, rc::Rc};
+
+use crate::{
+    error::report,
+    parser::{alias::Aliases, ast::FunctionDefinition},
+    proc::{job::Job, job_table::JobTable, ExternalProcesss},
+};
+
+use self::{options::ShellOptions, variables::Variables};
+
+pub mod options;
+pub mod variables;
+
+/// A pending `break` or `continue`, with the number of enclosing loops it
+/// still has to go through, or a pending `return`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ControlFlow {
+    Break(usize),
+    Continue(usize),
+    Return,
+}
 
 pub trait Shell {
-    fn add_job(&mut self, job: Job);
+    /// Adds a job to the job table and returns its id.
+    fn add_job(&mut self, job: Job) -> usize;
 
     fn last_exit_code(&self) -> i32;
 
+    fn set_last_exit_code(&mut self, code: i32);
+
     fn exit(&mut self);
 
     fn should_exit(&self) -> bool;
@@ -16,25 +39,166 @@ pub trait Shell {
     fn print_jobs(&self);
 
     fn get_job_pgid(&self, job_id: usize) -> anyhow::Result<i32>;
+
+    fn job_table(&self) -> &JobTable;
+
+    fn job_table_mut(&mut self) -> &mut JobTable;
+
+    /// Whether this shell is a forked copy of the main shell, in which case
+    /// job control is disabled.
+    fn is_subshell(&self) -> bool;
+
+    /// Turns the shell into a subshell, forgetting about the jobs of its
+    /// parent.
+    fn enter_subshell(&mut self);
+
+    /// Whether the jobs run in the foreground are given the terminal.
+    fn job_control(&self) -> bool {
+        self.options().monitor && !self.is_subshell()
+    }
+
+    /// Records a process substitution started while expanding a command,
+    /// along with the end of its pipe the shell keeps open for the command.
+    fn add_process_substitution(&mut self, process: ExternalProcesss, fd: RawFd);
+
+    /// Takes the process substitutions started since the last call, to
+    /// attach them to the job of the command.
+    fn take_process_substitutions(&mut self) -> Vec<(ExternalProcesss, RawFd)>;
+
+    /// The `break` or `continue` the commands being run have to stop for.
+    fn control_flow(&self) -> Option<ControlFlow>;
+
+    fn set_control_flow(&mut self, control_flow: Option<ControlFlow>);
+
+    /// The number of loops the command being run is nested in.
+    fn loop_depth(&self) -> usize;
+
+    fn set_loop_depth(&mut self, depth: usize);
+
+    fn function(&self, name: &str) -> Option<Rc<FunctionDefinition>>;
+
+    fn define_function(&mut self, function: FunctionDefinition);
+
+    /// Removes a function, returning whether it was defined.
+    fn unset_function(&mut self, name: &str) -> bool;
+
+    /// Enters a function called with `parameters`, which get their own
+    /// positional parameters and local variables.
+    fn push_frame(&mut self, parameters: Vec<String>);
+
+    /// Leaves the function entered last, restoring the positional parameters
+    /// and variables of its caller.
+    fn pop_frame(&mut self);
+
+    /// The number of function calls being run.
+    fn frame_depth(&self) -> usize;
+
+    /// The number of files being read by `source`.
+    fn source_depth(&self) -> usize;
+
+    fn set_source_depth(&mut self, depth: usize);
+
+    fn aliases(&self) -> &Aliases;
+
+    fn aliases_mut(&mut self) -> &mut Aliases;
+
+    fn variables(&self) -> &Variables;
+
+    fn variables_mut(&mut self) -> &mut Variables;
+
+    fn options(&self) -> &ShellOptions;
+
+    fn options_mut(&mut self) -> &mut ShellOptions;
+
+    fn get_var(&self, name: &str) -> Option<String> {
+        self.variables().value(name).map(String::from)
+    }
+
+    fn set_var(&mut self, name: &str, value: String) -> anyhow::Result<()> {
+        self.variables_mut().set(name, value)
+    }
+
+    /// The name of the shell or script, `$0`.
+    fn name(&self) -> &str;
+
+    fn set_name(&mut self, name: String);
+
+    /// The positional parameters, `$1`, `$2`, ...
+    fn positional_parameters(&self) -> &[String];
+
+    fn set_positional_parameters(&mut self, parameters: Vec<String>);
+
+    /// The process id of the main shell, `$$`, which is the same in
+    /// subshells.
+    fn pid(&self) -> i32;
+
+    /// The process id of the last job started in the background, `$!`.
+    fn last_background_pid(&self) -> Option<i32>;
+
+    fn set_last_background_pid(&mut self, pid: i32);
 }
 
-#[derive(Default)]
 pub struct DefaultShell {
     last_exit_code: i32,
     should_exit: bool,
+    subshell: bool,
 
+    name: String,
+    positional_parameters: Vec<String>,
+    // The positional parameters of the callers of the functions being run.
+    saved_positional_parameters: Vec<Vec<String>>,
+    pid: i32,
+    last_background_pid: Option<i32>,
+
+    control_flow: Option<ControlFlow>,
+    loop_depth: usize,
+    source_depth: usize,
+
+    functions: HashMap<String, Rc<FunctionDefinition>>,
+    aliases: Aliases,
+    variables: Variables,
+    options: ShellOptions,
     job_table: JobTable,
+    process_substitutions: Vec<(ExternalProcesss, RawFd)>,
+}
+
+impl Default for DefaultShell {
+    fn default() -> Self {
+        Self {
+            last_exit_code: 0,
+            should_exit: false,
+            subshell: false,
+            name: String::from("rjsh"),
+            positional_parameters: Vec::new(),
+            saved_positional_parameters: Vec::new(),
+            pid: std::process::id() as i32,
+            last_background_pid: None,
+            control_flow: None,
+            loop_depth: 0,
+            source_depth: 0,
+            functions: HashMap::new(),
+            aliases: Aliases::new(),
+            variables: Variables::from_env(),
+            options: ShellOptions::default(),
+            process_substitutions: Vec::new(),
+            job_table: JobTable::default(),
+        }
+    }
 }
 
 impl Shell for DefaultShell {
-    fn add_job(&mut self, job: Job) {
-        self.job_table.add_job(job);
+    fn add_job(&mut self, job: Job) -> usize {
+        self.job_table.add_job(job)
     }
 
     fn last_exit_code(&self) -> i32 {
         self.last_exit_code
     }
 
+    fn set_last_exit_code(&mut self, code: i32) {
+        self.last_exit_code = code;
+    }
+
     fn exit(&mut self) {
         self.should_exit = true;
     }
@@ -49,7 +213,7 @@ impl Shell for DefaultShell {
 
     fn update_jobs(&mut self) {
         if let Err(e) = self.job_table.update() {
-            eprintln!("rjsh: {e}");
+            report(e);
         }
     }
 
@@ -65,4 +229,136 @@ impl Shell for DefaultShell {
             .pgid
             .0)
     }
+
+    fn job_table(&self) -> &JobTable {
+        &self.job_table
+    }
+
+    fn job_table_mut(&mut self) -> &mut JobTable {
+        &mut self.job_table
+    }
+
+    fn is_subshell(&self) -> bool {
+        self.subshell
+    }
+
+    fn enter_subshell(&mut self) {
+        self.subshell = true;
+        self.job_table = JobTable::default();
+        self.process_substitutions.clear();
+    }
+
+    fn add_process_substitution(&mut self, process: ExternalProcesss, fd: RawFd) {
+        self.process_substitutions.push((process, fd));
+    }
+
+    fn take_process_substitutions(&mut self) -> Vec<(ExternalProcesss, RawFd)> {
+        std::mem::take(&mut self.process_substitutions)
+    }
+
+    fn control_flow(&self) -> Option<ControlFlow> {
+        self.control_flow
+    }
+
+    fn set_control_flow(&mut self, control_flow: Option<ControlFlow>) {
+        self.control_flow = control_flow;
+    }
+
+    fn loop_depth(&self) -> usize {
+        self.loop_depth
+    }
+
+    fn set_loop_depth(&mut self, depth: usize) {
+        self.loop_depth = depth;
+    }
+
+    fn function(&self, name: &str) -> Option<Rc<FunctionDefinition>> {
+        self.functions.get(name).cloned()
+    }
+
+    fn define_function(&mut self, function: FunctionDefinition) {
+        self.functions
+            .insert(function.name.clone(), Rc::new(function));
+    }
+
+    fn unset_function(&mut self, name: &str) -> bool {
+        self.functions.remove(name).is_some()
+    }
+
+    fn push_frame(&mut self, parameters: Vec<String>) {
+        let caller = std::mem::replace(&mut self.positional_parameters, parameters);
+        self.saved_positional_parameters.push(caller);
+        self.variables.push_scope();
+    }
+
+    fn pop_frame(&mut self) {
+        if let Some(caller) = self.saved_positional_parameters.pop() {
+            self.positional_parameters = caller;
+            self.variables.pop_scope();
+        }
+    }
+
+    fn frame_depth(&self) -> usize {
+        self.saved_positional_parameters.len()
+    }
+
+    fn source_depth(&self) -> usize {
+        self.source_depth
+    }
+
+    fn set_source_depth(&mut self, depth: usize) {
+        self.source_depth = depth;
+    }
+
+    fn aliases(&self) -> &Aliases {
+        &self.aliases
+    }
+
+    fn aliases_mut(&mut self) -> &mut Aliases {
+        &mut self.aliases
+    }
+
+    fn variables(&self) -> &Variables {
+        &self.variables
+    }
+
+    fn variables_mut(&mut self) -> &mut Variables {
+        &mut self.variables
+    }
+
+    fn options(&self) -> &ShellOptions {
+        &self.options
+    }
+
+    fn options_mut(&mut self) -> &mut ShellOptions {
+        &mut self.options
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    fn positional_parameters(&self) -> &[String] {
+        &self.positional_parameters
+    }
+
+    fn set_positional_parameters(&mut self, parameters: Vec<String>) {
+        self.positional_parameters = parameters;
+    }
+
+    fn pid(&self) -> i32 {
+        self.pid
+    }
+
+    fn last_background_pid(&self) -> Option<i32> {
+        self.last_background_pid
+    }
+
+    fn set_last_background_pid(&mut self, pid: i32) {
+        self.last_background_pid = Some(pid);
+    }
 }
@@ -0,0 +1,67 @@
+use anyhow::anyhow;
+
+/// The options changed with `set -o` and `shopt`.
+#[derive(Debug, Clone, Default)]
+pub struct ShellOptions {
+    /// Patterns match file names starting with a `.`.
+    pub dotglob: bool,
+    /// Patterns matching no file are an error.
+    pub failglob: bool,
+    /// `**` matches any number of directories.
+    pub globstar: bool,
+    /// `>` refuses to overwrite existing files.
+    pub noclobber: bool,
+    /// Patterns matching no file expand to nothing.
+    pub nullglob: bool,
+    /// Commands are read from a terminal. Fixed when the shell starts, so it
+    /// cannot be changed by name.
+    pub interactive: bool,
+    /// Jobs run in their own process groups and get the terminal in the
+    /// foreground. Fixed when the shell starts, like `interactive`.
+    pub monitor: bool,
+}
+
+impl ShellOptions {
+    pub const SET_NAMES: [&'static str; 1] = ["noclobber"];
+    pub const SHOPT_NAMES: [&'static str; 4] = ["dotglob", "failglob", "globstar", "nullglob"];
+
+    fn option_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "dotglob" => Some(&mut self.dotglob),
+            "failglob" => Some(&mut self.failglob),
+            "globstar" => Some(&mut self.globstar),
+            "noclobber" => Some(&mut self.noclobber),
+            "nullglob" => Some(&mut self.nullglob),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> anyhow::Result<bool> {
+        self.clone()
+            .option_mut(name)
+            .map(|value| *value)
+            .ok_or_else(|| anyhow!("{name}: invalid option name"))
+    }
+
+    pub fn set(&mut self, name: &str, value: bool) -> anyhow::Result<()> {
+        *self
+            .option_mut(name)
+            .ok_or_else(|| anyhow!("{name}: invalid option name"))? = value;
+        Ok(())
+    }
+
+    /// The single letter flags of the options that are on, as found in `$-`.
+    pub fn flags(&self) -> String {
+        let mut flags = String::new();
+        if self.interactive {
+            flags.push('i');
+        }
+        if self.monitor {
+            flags.push('m');
+        }
+        if self.noclobber {
+            flags.push('C');
+        }
+        flags
+    }
+}
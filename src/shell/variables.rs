@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+
+use crate::expansion::arithmetic;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Variable {
+    /// `None` for variables that were declared, e.g. with `export NAME`,
+    /// but never assigned.
+    pub value: Option<String>,
+    pub exported: bool,
+    pub readonly: bool,
+    pub integer: bool,
+}
+
+impl Variable {
+    /// The flags of the variable, as printed by `declare -p`.
+    pub fn flags(&self) -> String {
+        let mut flags = String::new();
+        if self.integer {
+            flags.push('i');
+        }
+        if self.readonly {
+            flags.push('r');
+        }
+        if self.exported {
+            flags.push('x');
+        }
+        if flags.is_empty() {
+            flags.push('-');
+        }
+        flags
+    }
+}
+
+/// The variables of the shell, only the exported ones are passed to the
+/// environment of the commands it runs.
+#[derive(Debug, Clone, Default)]
+pub struct Variables {
+    variables: HashMap<String, Variable>,
+    // The variables shadowed by `local` in each function being run, put
+    // back when it returns.
+    scopes: Vec<Vec<(String, Option<Variable>)>>,
+}
+
+impl Variables {
+    /// Creates a store holding the environment of the shell as exported
+    /// variables.
+    pub fn from_env() -> Self {
+        let variables = std::env::vars()
+            .map(|(name, value)| {
+                let variable = Variable {
+                    value: Some(value),
+                    exported: true,
+                    ..Variable::default()
+                };
+                (name, variable)
+            })
+            .collect();
+        Self {
+            variables,
+            scopes: Vec::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Variable> {
+        self.variables.get(name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.variables.get(name)?.value.as_deref()
+    }
+
+    fn get_writable(&mut self, name: &str) -> anyhow::Result<&mut Variable> {
+        let variable = self.variables.entry(name.to_string()).or_default();
+        if variable.readonly {
+            return Err(anyhow!("{name}: readonly variable"));
+        }
+        Ok(variable)
+    }
+
+    /// Assigns a variable, whose value is evaluated as an arithmetic
+    /// expression if it has the integer attribute.
+    pub fn set(&mut self, name: &str, value: String) -> anyhow::Result<()> {
+        let value = if self.get_writable(name)?.integer {
+            arithmetic::evaluate(&value, self)?.to_string()
+        } else {
+            value
+        };
+        self.get_writable(name)?.value = Some(value);
+        Ok(())
+    }
+
+    pub fn unset(&mut self, name: &str) -> anyhow::Result<()> {
+        if self.variables.get(name).is_some_and(|v| v.readonly) {
+            return Err(anyhow!("{name}: cannot unset: readonly variable"));
+        }
+        self.variables.remove(name);
+        Ok(())
+    }
+
+    /// Sets or clears the export attribute, declaring the variable if needed.
+    pub fn set_exported(&mut self, name: &str, exported: bool) {
+        self.variables.entry(name.to_string()).or_default().exported = exported;
+    }
+
+    /// Marks a variable as readonly, which cannot be undone.
+    pub fn set_readonly(&mut self, name: &str) {
+        self.variables.entry(name.to_string()).or_default().readonly = true;
+    }
+
+    pub fn set_integer(&mut self, name: &str, integer: bool) -> anyhow::Result<()> {
+        self.get_writable(name)?.integer = integer;
+        Ok(())
+    }
+
+    /// Starts the scope of the local variables of a function call.
+    pub fn push_scope(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    /// Ends the innermost scope, restoring the variables it shadowed.
+    pub fn pop_scope(&mut self) {
+        for (name, previous) in self.scopes.pop().into_iter().flatten().rev() {
+            self.restore(name, previous);
+        }
+    }
+
+    /// Puts back a variable as it was before being shadowed, `None` if it
+    /// was unset.
+    pub fn restore(&mut self, name: String, previous: Option<Variable>) {
+        match previous {
+            Some(variable) => self.variables.insert(name, variable),
+            None => self.variables.remove(&name),
+        };
+    }
+
+    /// Makes a variable local to the innermost scope, starting out unset.
+    /// Functions called from there see the local variable as well.
+    pub fn make_local(&mut self, name: &str) -> anyhow::Result<()> {
+        let Some(scope) = self.scopes.last_mut() else {
+            return Err(anyhow!("can only be used in a function"));
+        };
+        if scope.iter().any(|(local, _)| local == name) {
+            return Ok(());
+        }
+        if self.variables.get(name).is_some_and(|v| v.readonly) {
+            return Err(anyhow!("{name}: readonly variable"));
+        }
+        let previous = self.variables.insert(name.to_string(), Variable::default());
+        scope.push((name.to_string(), previous));
+        Ok(())
+    }
+
+    /// All the variables, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Variable)> {
+        let mut variables: Vec<_> = self.variables.iter().collect();
+        variables.sort_by_key(|&(name, _)| name);
+        variables.into_iter()
+    }
+
+    /// The `NAME=value` pairs of the exported variables, which make up the
+    /// environment of child processes.
+    pub fn environment(&self) -> Vec<String> {
+        self.iter()
+            .filter(|(_, variable)| variable.exported)
+            .filter_map(|(name, variable)| {
+                let value = variable.value.as_ref()?;
+                Some(format!("{name}={value}"))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_and_unset() {
+        let mut variables = Variables::default();
+        variables.set("a", "1".into()).unwrap();
+        assert_eq!(variables.value("a"), Some("1"));
+        variables.unset("a").unwrap();
+        assert_eq!(variables.value("a"), None);
+    }
+
+    #[test]
+    fn test_readonly() {
+        let mut variables = Variables::default();
+        variables.set("a", "1".into()).unwrap();
+        variables.set_readonly("a");
+        assert!(variables.set("a", "2".into()).is_err());
+        assert!(variables.unset("a").is_err());
+        assert_eq!(variables.value("a"), Some("1"));
+    }
+
+    #[test]
+    fn test_integer() {
+        let mut variables = Variables::default();
+        variables.set_integer("a", true).unwrap();
+        variables.set("a", " 42 ".into()).unwrap();
+        assert_eq!(variables.value("a"), Some("42"));
+        variables.set("a", "abc".into()).unwrap();
+        assert_eq!(variables.value("a"), Some("0"));
+        variables.set("b", "4".into()).unwrap();
+        variables.set("a", "b * 2 + 1".into()).unwrap();
+        assert_eq!(variables.value("a"), Some("9"));
+        assert!(variables.set("a", "1 +".into()).is_err());
+    }
+
+    #[test]
+    fn test_local_scopes() {
+        let mut variables = Variables::default();
+        assert!(variables.make_local("a").is_err());
+        variables.set("a", "global".into()).unwrap();
+
+        variables.push_scope();
+        variables.make_local("a").unwrap();
+        assert_eq!(variables.value("a"), None);
+        variables.set("a", "outer".into()).unwrap();
+        variables.make_local("b").unwrap();
+        variables.set("b", "1".into()).unwrap();
+
+        variables.push_scope();
+        assert_eq!(variables.value("a"), Some("outer"));
+        variables.make_local("a").unwrap();
+        variables.set("a", "inner".into()).unwrap();
+        variables.pop_scope();
+
+        assert_eq!(variables.value("a"), Some("outer"));
+        variables.pop_scope();
+        assert_eq!(variables.value("a"), Some("global"));
+        assert_eq!(variables.value("b"), None);
+    }
+
+    #[test]
+    fn test_environment() {
+        let mut variables = Variables::default();
+        variables.set("b", "2".into()).unwrap();
+        variables.set("a", "1".into()).unwrap();
+        variables.set("local", "3".into()).unwrap();
+        variables.set_exported("a", true);
+        variables.set_exported("b", true);
+        variables.set_exported("declared", true);
+        assert_eq!(variables.environment(), vec!["a=1", "b=2"]);
+    }
+}
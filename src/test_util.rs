@@ -0,0 +1,28 @@
+//! Helpers shared by the tests of several modules.
+
+use nix::{
+    sys::wait::{waitpid, WaitStatus},
+    unistd::{fork, ForkResult},
+};
+
+use crate::{exec::execute_list, parser::parse_command, proc::terminal, shell::DefaultShell};
+
+/// Parses and runs `text`, returning its exit code.
+pub fn run(shell: &mut DefaultShell, text: &str) -> i32 {
+    execute_list(shell, &parse_command(text).unwrap())
+}
+
+/// Runs `test` with a new shell in a child that catches SIGINT, so that the
+/// other tests do not see it, and checks that it returns true.
+pub fn catching_interrupts(test: impl FnOnce(&mut DefaultShell) -> bool) {
+    match unsafe { fork() }.unwrap() {
+        ForkResult::Child => {
+            let mut shell = DefaultShell::default();
+            let ok = terminal::catch_interrupt().is_ok() && test(&mut shell);
+            unsafe { nix::libc::_exit(i32::from(!ok)) };
+        }
+        ForkResult::Parent { child } => {
+            assert_eq!(waitpid(child, None).unwrap(), WaitStatus::Exited(child, 0));
+        }
+    }
+}